@@ -0,0 +1,62 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Index, Result};
+
+/// The largest tuple `Bundle` is implemented for.
+const MAX_FIELDS: usize = 8;
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Bundle can only be derived for structs",
+        ));
+    };
+    if data.fields.is_empty() || data.fields.len() > MAX_FIELDS {
+        return Err(Error::new_spanned(
+            &input.ident,
+            format!("bundles hold between 1 and {} components", MAX_FIELDS),
+        ));
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let types: Vec<_> = data.fields.iter().map(|field| &field.ty).collect();
+    let members: Vec<_> = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = Index::from(index);
+                quote!(#index)
+            }
+        })
+        .collect();
+
+    // Delegates to the tuple impl, which already writes one column per field.
+    Ok(quote! {
+        impl #impl_generics ::singleton_ecs::bundle::Bundle for #name #ty_generics #where_clause {
+            fn signature(
+                storage: &mut ::singleton_ecs::bundle::BundleStorage<'_>,
+            ) -> ::singleton_ecs::config::Signature {
+                <(#(#types,)*) as ::singleton_ecs::bundle::Bundle>::signature(storage)
+            }
+
+            fn insert_batch(
+                bundles: ::std::vec::Vec<Self>,
+                entities: &[::singleton_ecs::config::EntityType],
+                storage: &mut ::singleton_ecs::bundle::BundleStorage<'_>,
+            ) {
+                let tuples = bundles
+                    .into_iter()
+                    .map(|bundle| (#(bundle.#members,)*))
+                    .collect();
+                <(#(#types,)*) as ::singleton_ecs::bundle::Bundle>::insert_batch(
+                    tuples, entities, storage,
+                )
+            }
+        }
+    })
+}
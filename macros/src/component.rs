@@ -0,0 +1,141 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, LitStr, Path, Result};
+
+#[derive(Default)]
+struct ComponentAttrs {
+    tag: bool,
+    clone: bool,
+    scene: bool,
+    reflect: bool,
+    on_destroy: Option<Path>,
+}
+
+fn parse_attrs(input: &DeriveInput) -> Result<ComponentAttrs> {
+    let mut attrs = ComponentAttrs::default();
+    for attr in input.attrs.iter() {
+        if !attr.path().is_ident("component") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("storage") {
+                let storage: LitStr = meta.value()?.parse()?;
+                attrs.tag = match storage.value().as_str() {
+                    "dense" => false,
+                    "tag" => true,
+                    _ => {
+                        return Err(Error::new(
+                            storage.span(),
+                            "expected `\"dense\"` or `\"tag\"`",
+                        ))
+                    }
+                };
+            } else if meta.path.is_ident("clone") {
+                attrs.clone = true;
+            } else if meta.path.is_ident("scene") {
+                attrs.scene = true;
+            } else if meta.path.is_ident("reflect") {
+                attrs.reflect = true;
+            } else if meta.path.is_ident("on_destroy") {
+                attrs.on_destroy = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("unknown component attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let attrs = parse_attrs(&input)?;
+    if attrs.tag {
+        let field_less = matches!(&input.data, Data::Struct(data) if data.fields.is_empty());
+        if !field_less {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "tag components must be structs without fields",
+            ));
+        }
+        if attrs.clone || attrs.scene || attrs.reflect {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "tags have no data to clone, load or reflect",
+            ));
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let entity_destroyed = match &attrs.on_destroy {
+        Some(hook) => quote! {
+            fn entity_destroyed(&mut self, entity: ::singleton_ecs::config::EntityType) {
+                #hook(self, entity)
+            }
+        },
+        None => quote! {
+            fn entity_destroyed(&mut self, _entity: ::singleton_ecs::config::EntityType) {}
+        },
+    };
+
+    let mut register = vec![if attrs.tag {
+        quote!(singleton.register_tag::<Self>();)
+    } else {
+        quote!(singleton.register_component::<Self>();)
+    }];
+    if attrs.clone {
+        register.push(quote!(singleton.register_cloneable_component::<Self>();));
+    }
+    if attrs.scene {
+        register.push(quote!(singleton.register_scene_component::<Self>();));
+    }
+    if attrs.reflect {
+        register.push(quote!(singleton.register_reflect_component::<Self>();));
+    }
+    if attrs.on_destroy.is_some() {
+        register.push(quote!(singleton.register_destroy_hook::<Self>();));
+    }
+
+    // The same options, applied when the type is registered on first use.
+    let mut init_info = Vec::new();
+    if attrs.tag {
+        init_info.push(quote!(info.tag = true;));
+    }
+    if attrs.clone {
+        init_info.push(quote!(info.set_cloneable::<Self>();));
+    }
+    if attrs.scene {
+        init_info.push(quote!(info.set_scene::<Self>();));
+    }
+    if attrs.reflect {
+        init_info.push(quote!(info.set_reflect::<Self>();));
+    }
+    if attrs.on_destroy.is_some() {
+        init_info.push(quote!(info.set_destroy_hook::<Self>();));
+    }
+    let init_info = (!init_info.is_empty()).then(|| {
+        quote! {
+            fn init_info(info: &mut ::singleton_ecs::registry::ComponentInfo) {
+                #(#init_info)*
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::singleton_ecs::component::IComponent
+            for #name #ty_generics #where_clause
+        {
+            #entity_destroyed
+            #init_info
+        }
+
+        impl #impl_generics ::singleton_ecs::component::Component
+            for #name #ty_generics #where_clause
+        {
+            fn register(singleton: &mut ::singleton_ecs::singleton::EcsSingleton) {
+                #(#register)*
+            }
+        }
+    })
+}
@@ -0,0 +1,43 @@
+//! Derive macros for `singleton_ecs`. Use them through the main crate,
+//! which re-exports them next to the traits they implement.
+
+mod bundle;
+mod component;
+mod resource;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Implements `IComponent` and `Component`.
+///
+/// `#[component(...)]` takes:
+/// - `storage = "dense"` (default) or `storage = "tag"` for field-less
+///   markers that only live in signatures,
+/// - `clone`, `scene` and `reflect` to register the type for
+///   `clone_entity`, scene loading and reflection,
+/// - `on_destroy = path` to call `path(&mut self, entity)` when the owning
+///   entity is destroyed.
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    component::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `Bundle` for a struct whose fields are components, so it can be
+/// passed to `spawn_batch` like the equivalent tuple.
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bundle::derive(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements the `Resource` marker trait.
+#[proc_macro_derive(Resource)]
+pub fn derive_resource(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    resource::derive(input).into()
+}
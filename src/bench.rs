@@ -0,0 +1,421 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    hint::black_box,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use glam::Vec3;
+use singleton_ecs::{
+    builtin::{register_builtins, RigidBody, Transform},
+    config::{EntityType, Signature, MAX_ENTITIES},
+    singleton::EcsSingleton,
+};
+
+use crate::runner::RunnerError;
+
+pub const USAGE: &str =
+    "usage: singleton_ecs bench [--entities N] [--iterations N] [--out PATH] [--baseline PATH]";
+
+/// First line of every report; bump the version when columns change.
+const REPORT_HEADER: &str = "# singleton_ecs bench v1\nname\tentities\titerations\tmean_ns\tmin_ns";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchConfig {
+    pub entities: usize,
+    pub iterations: u32,
+    pub out: Option<PathBuf>,
+    /// A previous report to compare against.
+    pub baseline: Option<PathBuf>,
+}
+
+impl BenchConfig {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, RunnerError> {
+        let mut config = BenchConfig {
+            entities: 1000,
+            iterations: 50,
+            out: None,
+            baseline: None,
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = match arg.as_str() {
+                "--entities" | "--iterations" | "--out" | "--baseline" => args.next(),
+                _ => return Err(usage(format!("unexpected argument {}", arg))),
+            }
+            .ok_or_else(|| usage(format!("{} needs a value", arg)))?;
+            match arg.as_str() {
+                "--entities" => config.entities = parse(&arg, &value)?,
+                "--iterations" => config.iterations = parse(&arg, &value)?,
+                "--out" => config.out = Some(PathBuf::from(value)),
+                _ => config.baseline = Some(PathBuf::from(value)),
+            }
+        }
+        if config.iterations == 0 || config.entities == 0 {
+            return Err(usage(
+                "--entities and --iterations must be positive".to_string(),
+            ));
+        }
+        if config.entities > MAX_ENTITIES as usize {
+            return Err(usage(format!("--entities can be at most {}", MAX_ENTITIES)));
+        }
+        Ok(config)
+    }
+}
+
+fn usage(message: String) -> RunnerError {
+    RunnerError::BenchUsage(message)
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, RunnerError> {
+    value
+        .parse()
+        .map_err(|_| usage(format!("{} needs a valid value", flag)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchResult {
+    pub name: String,
+    pub entities: usize,
+    pub iterations: u32,
+    pub mean: Duration,
+    pub min: Duration,
+}
+
+/// Times `routine` `iterations` times after one untimed warm-up run. The
+/// routine must leave the world as it found it.
+fn measure<F: FnMut()>(
+    name: &str,
+    entities: usize,
+    iterations: u32,
+    mut routine: F,
+) -> BenchResult {
+    routine();
+    let mut total = Duration::ZERO;
+    let mut min = Duration::MAX;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        routine();
+        let elapsed = start.elapsed();
+        total += elapsed;
+        min = min.min(elapsed);
+    }
+    BenchResult {
+        name: name.to_string(),
+        entities,
+        iterations,
+        mean: total / iterations,
+        min,
+    }
+}
+
+fn world() -> EcsSingleton {
+    let mut singleton = EcsSingleton::new();
+    register_builtins(&mut singleton);
+    singleton
+}
+
+fn spawn_bodies(singleton: &mut EcsSingleton, count: usize) -> Vec<EntityType> {
+    singleton.spawn_batch((0..count).map(|i| {
+        let position = Vec3::splat(i as f32);
+        let force = Vec3::Y;
+        let body = RigidBody {
+            force,
+            ..Default::default()
+        };
+        (Transform { position }, body)
+    }))
+}
+
+fn entity_churn(config: &BenchConfig) -> BenchResult {
+    let mut singleton = world();
+    measure("entity_churn", config.entities, config.iterations, || {
+        let entities: Vec<EntityType> = (0..config.entities)
+            .map(|_| singleton.create_entity())
+            .collect();
+        for entity in entities {
+            singleton.destroy_entity(entity);
+        }
+    })
+}
+
+fn component_add_remove(config: &BenchConfig) -> BenchResult {
+    let mut singleton = world();
+    let entities: Vec<EntityType> = (0..config.entities)
+        .map(|_| singleton.create_entity())
+        .collect();
+    measure(
+        "component_add_remove",
+        config.entities,
+        config.iterations,
+        || {
+            for entity in entities.iter() {
+                singleton.add_component(*entity, Transform::default());
+            }
+            for entity in entities.iter() {
+                singleton.remove_component::<Transform>(*entity);
+            }
+        },
+    )
+}
+
+fn component_random_access(config: &BenchConfig) -> BenchResult {
+    let mut singleton = world();
+    let mut entities = spawn_bodies(&mut singleton, config.entities);
+    // Fixed-seed shuffle so every run reads in the same order.
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    for i in (1..entities.len()).rev() {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        entities.swap(i, (seed % (i as u64 + 1)) as usize);
+    }
+    measure(
+        "component_random_access",
+        config.entities,
+        config.iterations,
+        || {
+            let mut sum = Vec3::ZERO;
+            for entity in entities.iter() {
+                sum += singleton.get_component::<Transform>(*entity).position;
+            }
+            black_box(sum);
+        },
+    )
+}
+
+fn system_iteration(config: &BenchConfig) -> BenchResult {
+    let mut singleton = world();
+    spawn_bodies(&mut singleton, config.entities);
+    let mut query = singleton.query::<(&mut Transform, &RigidBody)>();
+    measure(
+        "system_iteration",
+        config.entities,
+        config.iterations,
+        || {
+            query
+                .query(&singleton)
+                .for_each(|(transform, body)| transform.position += body.velocity);
+        },
+    )
+}
+
+/// A system type per index, so many can be registered at once.
+struct Matcher<const N: usize>;
+
+macro_rules! register_matchers {
+    ($singleton:ident, $($n:literal),+) => {
+        $(
+            $singleton.register_system::<Matcher<$n>>();
+            let signature = matcher_signature(&mut $singleton, $n);
+            $singleton.set_system_signature::<Matcher<$n>>(signature);
+        )+
+    };
+}
+
+fn matcher_signature(singleton: &mut EcsSingleton, index: usize) -> Signature {
+    let mut signature = Signature::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    if index % 2 == 1 {
+        signature.insert(singleton.get_component_type::<RigidBody>() as usize);
+    }
+    if index.is_multiple_of(3) {
+        let children = singleton.get_component_type_by_name("Children").unwrap();
+        signature.insert(children as usize);
+    }
+    signature
+}
+
+/// A world with sixteen systems matching on `Transform` and `RigidBody`.
+fn matcher_world() -> EcsSingleton {
+    let mut singleton = world();
+    register_matchers!(singleton, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+    singleton
+}
+
+fn signature_matching(config: &BenchConfig) -> BenchResult {
+    let mut singleton = matcher_world();
+    let entities: Vec<EntityType> = spawn_bodies(&mut singleton, config.entities);
+    singleton.system_infos();
+    measure(
+        "signature_matching_16_systems",
+        config.entities,
+        config.iterations,
+        || {
+            for entity in entities.iter() {
+                singleton.remove_component::<RigidBody>(*entity);
+            }
+            black_box(singleton.system_infos());
+            for entity in entities.iter() {
+                singleton.add_component(*entity, RigidBody::default());
+            }
+            black_box(singleton.system_infos());
+        },
+    )
+}
+
+/// Spawns bodies one component at a time, as `spawn_batch` replaces.
+fn spawn_per_entity(config: &BenchConfig) -> BenchResult {
+    let mut singleton = matcher_world();
+    measure(
+        "spawn_per_entity_16_systems",
+        config.entities,
+        config.iterations,
+        || {
+            let entities: Vec<EntityType> = (0..config.entities)
+                .map(|i| {
+                    let entity = singleton.create_entity();
+                    let position = Vec3::splat(i as f32);
+                    singleton.add_component(entity, Transform { position });
+                    singleton.add_component(entity, RigidBody::default());
+                    entity
+                })
+                .collect();
+            singleton.despawn_batch(&entities);
+        },
+    )
+}
+
+fn spawn_batch(config: &BenchConfig) -> BenchResult {
+    let mut singleton = matcher_world();
+    measure(
+        "spawn_batch_16_systems",
+        config.entities,
+        config.iterations,
+        || {
+            let entities = spawn_bodies(&mut singleton, config.entities);
+            singleton.despawn_batch(&entities);
+        },
+    )
+}
+
+pub fn run_suite(config: &BenchConfig) -> Vec<BenchResult> {
+    vec![
+        entity_churn(config),
+        component_add_remove(config),
+        component_random_access(config),
+        system_iteration(config),
+        signature_matching(config),
+        spawn_per_entity(config),
+        spawn_batch(config),
+    ]
+}
+
+/// Tab-separated, one benchmark per line, in suite order.
+pub fn format_report(results: &[BenchResult]) -> String {
+    let mut report = format!("{}\n", REPORT_HEADER);
+    for result in results {
+        writeln!(
+            report,
+            "{}\t{}\t{}\t{}\t{}",
+            result.name,
+            result.entities,
+            result.iterations,
+            result.mean.as_nanos(),
+            result.min.as_nanos()
+        )
+        .unwrap();
+    }
+    report
+}
+
+/// Mean time per benchmark name from a report written by `format_report`.
+pub fn parse_report(report: &str) -> Result<HashMap<String, u128>, String> {
+    let mut lines = report.lines();
+    for expected in REPORT_HEADER.lines() {
+        if lines.next() != Some(expected) {
+            return Err("not a singleton_ecs bench v1 report".to_string());
+        }
+    }
+    lines
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let columns: Vec<&str> = line.split('\t').collect();
+            match columns.as_slice() {
+                [name, _, _, mean, _] => mean
+                    .parse()
+                    .map(|mean| (name.to_string(), mean))
+                    .map_err(|_| format!("bad mean in {:?}", line)),
+                _ => Err(format!("bad line {:?}", line)),
+            }
+        })
+        .collect()
+}
+
+/// One line per benchmark with the change in mean time against `baseline`.
+pub fn format_comparison(results: &[BenchResult], baseline: &HashMap<String, u128>) -> String {
+    let mut comparison = String::new();
+    for result in results {
+        let mean = result.mean.as_nanos();
+        match baseline.get(&result.name) {
+            Some(&before) if before > 0 => {
+                let change = (mean as f64 - before as f64) / before as f64 * 100.0;
+                writeln!(
+                    comparison,
+                    "{}\t{} -> {} ns\t{:+.1}%",
+                    result.name, before, mean, change
+                )
+            }
+            _ => writeln!(comparison, "{}\tnew", result.name),
+        }
+        .unwrap();
+    }
+    comparison
+}
+
+pub fn run(config: &BenchConfig) -> Result<(), RunnerError> {
+    let baseline = match &config.baseline {
+        Some(path) => {
+            let report = fs::read_to_string(path).map_err(|e| RunnerError::Io(e.to_string()))?;
+            Some(parse_report(&report).map_err(RunnerError::Io)?)
+        }
+        None => None,
+    };
+
+    let results = run_suite(config);
+    let report = format_report(&results);
+    match &config.out {
+        Some(path) => fs::write(path, &report).map_err(|e| RunnerError::Io(e.to_string()))?,
+        None => print!("{}", report),
+    }
+    if let Some(baseline) = baseline {
+        print!("{}", format_comparison(&results, &baseline));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod bench {
+    use super::*;
+
+    #[test]
+    fn test_suite_report_round_trips() {
+        let config =
+            BenchConfig::from_args(["--entities", "20", "--iterations", "1"].map(String::from))
+                .unwrap();
+        let results = run_suite(&config);
+        assert_eq!(results.len(), 7);
+
+        let report = format_report(&results);
+        let parsed = parse_report(&report).unwrap();
+        assert_eq!(parsed.len(), 7);
+        assert_eq!(parsed["system_iteration"], results[3].mean.as_nanos());
+
+        let comparison = format_comparison(&results, &parsed);
+        assert!(comparison.starts_with("entity_churn\t"));
+        assert!(comparison.lines().all(|line| line.ends_with("+0.0%")));
+        assert!(parse_report("name\tmean").is_err());
+    }
+
+    #[test]
+    fn test_rejects_more_entities_than_a_world_holds() {
+        let args = ["--entities", "5001"].map(String::from);
+        let error = BenchConfig::from_args(args).unwrap_err();
+        assert!(matches!(error, RunnerError::BenchUsage(_)));
+        let message = error.to_string();
+        assert!(message.ends_with(USAGE));
+        assert!(!message.contains(crate::runner::USAGE));
+    }
+}
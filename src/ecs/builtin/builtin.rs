@@ -0,0 +1,96 @@
+use glam::Vec3;
+use serde::Deserialize;
+
+use crate::ecs::ecs::{
+    component::IComponent, config::EntityType, param::Res, query::Query,
+    resource_manager::Resource, singleton::EcsSingleton,
+};
+
+/// Where an entity is in the world.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Transform {
+    pub position: Vec3,
+}
+
+impl IComponent for Transform {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+crate::impl_reflect_struct!(Transform { position });
+
+/// Moves its entity's `Transform` every tick. `force` is applied as an
+/// acceleration, scaled by `1 / mass`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RigidBody {
+    #[serde(default)]
+    pub velocity: Vec3,
+    #[serde(default)]
+    pub force: Vec3,
+    #[serde(default = "default_mass")]
+    pub mass: f32,
+}
+
+fn default_mass() -> f32 {
+    1.0
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            velocity: Vec3::ZERO,
+            force: Vec3::ZERO,
+            mass: default_mass(),
+        }
+    }
+}
+
+impl IComponent for RigidBody {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+crate::impl_reflect_struct!(RigidBody {
+    velocity,
+    force,
+    mass
+});
+
+/// Simulation clock, advanced by whoever drives `EcsSingleton::update`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Time {
+    /// Seconds covered by the current tick.
+    pub delta: f32,
+    pub elapsed: f32,
+    pub tick: u64,
+}
+
+impl Resource for Time {}
+
+impl Time {
+    pub fn advance(&mut self, delta: f32) {
+        self.delta = delta;
+        self.elapsed += delta;
+        self.tick += 1;
+    }
+}
+
+/// Semi-implicit Euler step for every `RigidBody`.
+pub fn integrate(mut query: Query<(&mut Transform, &mut RigidBody)>, time: Res<Time>) {
+    query.for_each(|(transform, body)| {
+        body.velocity += body.force / body.mass * time.delta;
+        transform.position += body.velocity * time.delta;
+    });
+}
+
+/// Registers the components scene files can use out of the box, the `Time`
+/// resource and the systems that act on them.
+pub fn register_builtins(singleton: &mut EcsSingleton) {
+    singleton.register_scene_component::<Transform>();
+    singleton.register_reflect_component::<Transform>();
+    singleton.register_cloneable_component::<Transform>();
+    singleton.register_scene_component::<RigidBody>();
+    singleton.register_reflect_component::<RigidBody>();
+    singleton.register_cloneable_component::<RigidBody>();
+
+    singleton.insert_resource(Time::default());
+    singleton.register_system_func(integrate);
+}
@@ -0,0 +1,82 @@
+use std::any::type_name;
+
+use crate::ecs::ecs::{
+    component::IComponent,
+    component_manager::ComponentManager,
+    config::{ComponentType, EntityType, Signature},
+};
+
+/// A tuple of components spawned together, e.g. `(Transform, RigidBody)`.
+pub trait Bundle: Sized {
+    /// Signature of an entity holding exactly this bundle, registering its
+    /// types on first use. Panics if the bundle holds a type twice.
+    fn signature(storage: &mut BundleStorage<'_>) -> Signature;
+
+    /// Writes each component column in one pass, locking every component
+    /// array once for the whole batch.
+    fn insert_batch(bundles: Vec<Self>, entities: &[EntityType], storage: &mut BundleStorage<'_>);
+}
+
+/// The part of a world's component storage a `Bundle` works with.
+pub struct BundleStorage<'a> {
+    manager: &'a mut ComponentManager,
+}
+
+impl<'a> BundleStorage<'a> {
+    pub(crate) fn new(manager: &'a mut ComponentManager) -> Self {
+        Self { manager }
+    }
+
+    /// The id of `T`, registering it on first use.
+    pub fn component_type<T: IComponent>(&mut self) -> ComponentType {
+        self.manager.register_on_first_use::<T>();
+        self.manager.get_component_type::<T>()
+    }
+
+    /// Stores `components[i]` on `entities[i]`.
+    pub fn add_component_batch<T: IComponent>(
+        &mut self,
+        entities: &[EntityType],
+        components: Vec<T>,
+    ) {
+        self.manager.add_component_batch(entities, components);
+    }
+}
+
+macro_rules! impl_bundle {
+    ($($index:tt $name:ident),+) => {
+        impl<$($name: IComponent),+> Bundle for ($($name,)+) {
+            fn signature(storage: &mut BundleStorage<'_>) -> Signature {
+                let mut signature = Signature::default();
+                $(signature.insert(storage.component_type::<$name>() as usize);)+
+                assert!(
+                    signature.len() == [$($index),+].len(),
+                    "Bundle {} holds a component type more than once.",
+                    type_name::<Self>()
+                );
+                signature
+            }
+
+            fn insert_batch(
+                bundles: Vec<Self>,
+                entities: &[EntityType],
+                storage: &mut BundleStorage<'_>,
+            ) {
+                let mut columns = ($(Vec::<$name>::with_capacity(bundles.len()),)+);
+                for bundle in bundles {
+                    $(columns.$index.push(bundle.$index);)+
+                }
+                $(storage.add_component_batch(entities, columns.$index);)+
+            }
+        }
+    };
+}
+
+impl_bundle!(0 A);
+impl_bundle!(0 A, 1 B);
+impl_bundle!(0 A, 1 B, 2 C);
+impl_bundle!(0 A, 1 B, 2 C, 3 D);
+impl_bundle!(0 A, 1 B, 2 C, 3 D, 4 E);
+impl_bundle!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F);
+impl_bundle!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G);
+impl_bundle!(0 A, 1 B, 2 C, 3 D, 4 E, 5 F, 6 G, 7 H);
@@ -0,0 +1,74 @@
+use std::any::{Any, TypeId};
+
+use crate::ecs::ecs::{
+    component::{BoxedComponent, IComponent},
+    config::ComponentType,
+    config::EntityType,
+    singleton::EcsSingleton,
+};
+
+/// Copies a type-erased component, captured when a component type is
+/// registered as cloneable.
+pub type ComponentCloner = fn(&dyn Any) -> BoxedComponent;
+
+/// What `EcsSingleton::clone_entity_with` does with components that can't be
+/// cloned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClonePolicy {
+    #[default]
+    SkipNonCloneable,
+    ErrorOnNonCloneable,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CloneError {
+    NotCloneable(String),
+}
+
+/// A component taken or copied out of storage, ready to be attached to
+/// another entity.
+pub enum DetachedComponent {
+    Typed(TypeId, BoxedComponent),
+    Dynamic(ComponentType, Vec<u8>),
+    Tag(ComponentType),
+}
+
+/// A tuple of cloneable component types copied together by
+/// `EcsSingleton::copy_components`.
+pub trait ComponentSet {
+    fn copy(singleton: &mut EcsSingleton, src: EntityType, dst: EntityType);
+}
+
+fn copy_one<T: IComponent + Clone>(singleton: &mut EcsSingleton, src: EntityType, dst: EntityType) {
+    if !singleton.has_component::<T>(src) {
+        return;
+    }
+    if singleton.component_manager().is_tag(TypeId::of::<T>()) {
+        return singleton.add_tag::<T>(dst);
+    }
+    let value = singleton.get_component::<T>(src).clone();
+    if singleton.has_component::<T>(dst) {
+        singleton.get_component_mut::<T, _, ()>(dst, |c| *c = value);
+    } else {
+        singleton.add_component(dst, value);
+    }
+}
+
+macro_rules! impl_component_set {
+    ($($name:ident),+) => {
+        impl<$($name: IComponent + Clone),+> ComponentSet for ($($name,)+) {
+            fn copy(singleton: &mut EcsSingleton, src: EntityType, dst: EntityType) {
+                $(copy_one::<$name>(singleton, src, dst);)+
+            }
+        }
+    };
+}
+
+impl_component_set!(A);
+impl_component_set!(A, B);
+impl_component_set!(A, B, C);
+impl_component_set!(A, B, C, D);
+impl_component_set!(A, B, C, D, E);
+impl_component_set!(A, B, C, D, E, F);
+impl_component_set!(A, B, C, D, E, F, G);
+impl_component_set!(A, B, C, D, E, F, G, H);
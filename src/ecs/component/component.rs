@@ -1,19 +1,96 @@
 use core::panic;
 use std::{any::Any, collections::HashMap};
 
-use crate::ecs::ecs::config::{EntityType, MAX_ENTITIES};
+use crate::ecs::ecs::{
+    config::{EntityType, MAX_ENTITIES},
+    registry::ComponentInfo,
+    singleton::EcsSingleton,
+};
 
-pub trait IComponent: Any {
+/// A type-erased component as held by a `ComponentArray`.
+pub type BoxedComponent = Box<dyn Any + Send + Sync>;
+
+pub trait IComponent: Any + Send + Sync {
     fn entity_destroyed(&mut self, entity: EntityType);
+
+    fn component_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Fills in the metadata of a type registered on first use, see
+    /// `EcsSingleton::set_auto_register_components`. `#[derive(Component)]`
+    /// sets the same storage and options as `Component::register`.
+    fn init_info(_info: &mut ComponentInfo)
+    where
+        Self: Sized,
+    {
+    }
+}
+
+/// A component that knows how it should be registered: its storage and
+/// whether it can be cloned, loaded from scenes or reflected. Usually
+/// implemented with `#[derive(Component)]`.
+pub trait Component: IComponent {
+    fn register(singleton: &mut EcsSingleton);
+}
+
+/// A way a component array's bookkeeping can disagree with itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageProblem {
+    Size {
+        size: usize,
+        entity_to_index: usize,
+        index_to_entity: usize,
+    },
+    /// `entity` maps to `index`, which is out of range or maps back to a
+    /// different entity.
+    Index { entity: EntityType, index: usize },
+    /// The slot at `index` is filled when it should be empty, or the other
+    /// way around.
+    Slot { index: usize },
+}
+
+/// Cross-checks the two index maps of a dense array against its size.
+pub(crate) fn check_index_maps(
+    entity_to_index_map: &HashMap<EntityType, usize>,
+    index_to_entity_map: &HashMap<usize, EntityType>,
+    size: usize,
+) -> Vec<StorageProblem> {
+    let mut problems = Vec::new();
+    if entity_to_index_map.len() != size || index_to_entity_map.len() != size {
+        problems.push(StorageProblem::Size {
+            size,
+            entity_to_index: entity_to_index_map.len(),
+            index_to_entity: index_to_entity_map.len(),
+        });
+    }
+    let mut entries: Vec<(&EntityType, &usize)> = entity_to_index_map.iter().collect();
+    entries.sort_unstable();
+    for (entity, index) in entries {
+        if *index >= size || index_to_entity_map.get(index) != Some(entity) {
+            problems.push(StorageProblem::Index {
+                entity: *entity,
+                index: *index,
+            });
+        }
+    }
+    problems
 }
 
 #[derive(Debug)]
-pub struct ComponentArray {
-    component_array: [Option<Box<dyn Any>>; MAX_ENTITIES as usize],
+pub(crate) struct ComponentArray {
+    component_array: [Option<BoxedComponent>; MAX_ENTITIES as usize],
     entity_to_index_map: HashMap<EntityType, usize>,
     index_to_entity_map: HashMap<usize, EntityType>,
     size: usize,
 }
+
+impl Default for ComponentArray {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ComponentArray {
     pub fn new() -> Self {
         Self {
@@ -25,6 +102,10 @@ impl ComponentArray {
     }
 
     pub fn insert_data<T: IComponent + 'static>(&mut self, entity: EntityType, component: T) {
+        self.insert_boxed(entity, Box::new(component));
+    }
+
+    pub fn insert_boxed(&mut self, entity: EntityType, component: BoxedComponent) {
         let new_index = self.size;
         assert!(
             !self.entity_to_index_map.contains_key(&entity),
@@ -38,10 +119,33 @@ impl ComponentArray {
 
         self.entity_to_index_map.insert(entity, new_index);
         self.index_to_entity_map.insert(new_index, entity);
-        self.component_array[new_index] = Some(Box::new(component));
+        self.component_array[new_index] = Some(component);
         self.size += 1;
     }
 
+    /// Removes the component of `entity` and hands it back instead of
+    /// dropping it.
+    pub fn take_boxed(&mut self, entity: EntityType) -> Option<BoxedComponent> {
+        let index = *self.entity_to_index_map.get(&entity)?;
+        let component = self.component_array[index].take();
+        self.remove_data(entity);
+        component
+    }
+
+    pub fn get_any(&self, entity: EntityType) -> Option<&dyn Any> {
+        let index = *self.entity_to_index_map.get(&entity)?;
+        self.component_array[index]
+            .as_deref()
+            .map(|c| c as &dyn Any)
+    }
+
+    pub fn get_any_mut(&mut self, entity: EntityType) -> Option<&mut dyn Any> {
+        let index = *self.entity_to_index_map.get(&entity)?;
+        self.component_array[index]
+            .as_deref_mut()
+            .map(|c| c as &mut dyn Any)
+    }
+
     pub fn get_data_mut<T: Any>(&mut self, entity: EntityType) -> &mut T {
         assert!(
             self.entity_to_index_map.contains_key(&entity),
@@ -52,28 +156,88 @@ impl ComponentArray {
             .as_mut()
             .and_then(|c| c.downcast_mut::<T>())
         {
-            return v;
+            v
         } else {
             panic!("Cannot get the entity component from the given entity");
         }
     }
-    pub fn get_data<T: Any>(&mut self, entity: EntityType) -> &T {
+    pub fn get_data<T: Any>(&self, entity: EntityType) -> &T {
         assert!(
             self.entity_to_index_map.contains_key(&entity),
             "Retrieving non-existent component."
         );
 
         if let Some(v) = self.component_array[self.entity_to_index_map[&entity]]
-            .as_mut()
+            .as_ref()
             .unwrap()
             .downcast_ref::<T>()
         {
-            return v;
+            v
         } else {
             panic!("Cant get data from entity");
         }
     }
-    pub fn get_components(&mut self) -> &[Option<Box<dyn Any>>] {
+    /// Components of `entities`, in the same order. Walks the dense storage
+    /// once, so the returned borrows are disjoint and can be handed to
+    /// different threads.
+    pub fn get_many_mut<T: Any>(&mut self, entities: &[EntityType]) -> Vec<&mut T> {
+        let mut slots: Vec<Option<&mut Option<BoxedComponent>>> = self.component_array[..self.size]
+            .iter_mut()
+            .map(Some)
+            .collect();
+        entities
+            .iter()
+            .map(|entity| {
+                let index = *self
+                    .entity_to_index_map
+                    .get(entity)
+                    .expect("Retrieving non-existent component.");
+                slots[index]
+                    .take()
+                    .and_then(|slot| slot.as_mut())
+                    .and_then(|component| component.downcast_mut::<T>())
+                    .expect("Cannot get the entity component from the given entity")
+            })
+            .collect()
+    }
+
+    pub fn get_many<T: Any>(&self, entities: &[EntityType]) -> Vec<&T> {
+        entities
+            .iter()
+            .map(|entity| {
+                let index = *self
+                    .entity_to_index_map
+                    .get(entity)
+                    .expect("Retrieving non-existent component.");
+                self.component_array[index]
+                    .as_ref()
+                    .and_then(|component| component.downcast_ref::<T>())
+                    .expect("Cant get data from entity")
+            })
+            .collect()
+    }
+
+    /// Entities with a component stored here, in no particular order.
+    pub fn entities(&self) -> impl Iterator<Item = EntityType> + '_ {
+        self.entity_to_index_map.keys().copied()
+    }
+
+    pub fn check(&self) -> Vec<StorageProblem> {
+        let mut problems = check_index_maps(
+            &self.entity_to_index_map,
+            &self.index_to_entity_map,
+            self.size,
+        );
+        for (index, slot) in self.component_array.iter().enumerate() {
+            if slot.is_some() != (index < self.size) {
+                problems.push(StorageProblem::Slot { index });
+            }
+        }
+        problems
+    }
+
+    #[cfg(test)]
+    pub fn get_components(&self) -> &[Option<BoxedComponent>] {
         &self.component_array
     }
 
@@ -86,8 +250,9 @@ impl ComponentArray {
         let index_of_removed_entity = self.entity_to_index_map[&entity];
         let index_of_last_element = self.size - 1;
 
-        self.component_array[index_of_removed_entity] =
-            self.component_array[index_of_last_element].take();
+        self.component_array
+            .swap(index_of_removed_entity, index_of_last_element);
+        self.component_array[index_of_last_element] = None;
 
         let entity_of_last_element = self.index_to_entity_map[&index_of_last_element];
         self.entity_to_index_map
@@ -111,6 +276,7 @@ impl IComponent for ComponentArray {
 
 impl std::fmt::Debug for dyn IComponent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("IComponents").finish()
+        f.debug_struct(self.component_name())
+            .finish_non_exhaustive()
     }
 }
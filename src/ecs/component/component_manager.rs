@@ -1,46 +1,338 @@
-use std::any::{Any, TypeId};
+use std::alloc::Layout;
+use std::any::{type_name, Any, TypeId};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::mem;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
 
-use crate::ecs::ecs::config::{ComponentType, EntityType};
+use serde::de::DeserializeOwned;
 
-use super::component::{ComponentArray, IComponent};
+use crate::ecs::ecs::{
+    config::{ComponentType, EntityType},
+    reflect::Reflect,
+};
 
+use super::clone::DetachedComponent;
+use super::component::{BoxedComponent, ComponentArray, IComponent, StorageProblem};
+use super::dynamic::{DynamicComponentArray, DynamicDropFn};
+use super::registry::{ComponentInfo, ComponentRegistry, FreezeError, SharedRegistry};
+
+/// The state of one component array, from `ComponentManager::check_storage`.
+#[derive(Debug, Clone)]
+pub struct StorageReport {
+    pub info: ComponentInfo,
+    pub problems: Vec<StorageProblem>,
+    pub entities: Vec<EntityType>,
+}
+
+/// Storage for one world. The registry that assigns `ComponentType` ids may
+/// be shared with other worlds; arrays are created here the first time a
+/// registered type is stored.
 #[derive(Debug)]
 pub struct ComponentManager {
-    component_types: HashMap<TypeId, ComponentType>,
-    component_arrays: HashMap<TypeId, Arc<Mutex<ComponentArray>>>,
-    next_component_type: ComponentType,
+    registry: SharedRegistry,
+    component_arrays: HashMap<TypeId, Arc<RwLock<ComponentArray>>>,
+    dynamic_arrays: HashMap<ComponentType, Arc<Mutex<DynamicComponentArray>>>,
+}
+
+impl Default for ComponentManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ComponentManager {
     pub fn new() -> Self {
+        Self::with_registry(Arc::new(RwLock::new(ComponentRegistry::new())))
+    }
+
+    pub fn with_registry(registry: SharedRegistry) -> Self {
         Self {
-            component_types: HashMap::new(),
+            registry,
             component_arrays: HashMap::new(),
-            next_component_type: 0,
+            dynamic_arrays: HashMap::new(),
         }
     }
 
     pub fn register_component<T: Any>(&mut self) {
         let type_id = TypeId::of::<T>();
+        self.registry.write().unwrap().register::<T>();
 
+        let component_array = ComponentArray::new();
+        self.component_arrays
+            .insert(type_id, Arc::new(RwLock::new(component_array)));
+    }
+
+    /// Registers a zero-sized marker component. Tags get a `ComponentType`
+    /// and take part in signatures, but never allocate storage.
+    pub fn register_tag<T: Any>(&mut self) {
         assert!(
-            !self.component_types.contains_key(&type_id),
-            "Registering component type more than once."
+            std::mem::size_of::<T>() == 0,
+            "Tag components must be zero-sized."
         );
-        let component_array = ComponentArray::new();
+        self.registry.write().unwrap().register::<T>().tag = true;
+    }
 
-        self.component_types
-            .insert(type_id, self.next_component_type);
-        self.component_arrays
-            .insert(type_id, Arc::new(Mutex::new(component_array)));
+    pub fn is_tag(&self, type_id: TypeId) -> bool {
+        self.registry().get(type_id).is_some_and(|info| info.tag)
+    }
+
+    /// Registers a component that can be built from scene data under its
+    /// short type name, e.g. `Transform`.
+    pub fn register_scene_component<T: IComponent + DeserializeOwned>(&mut self) {
+        self.update_info::<T>(ComponentInfo::set_scene::<T>);
+    }
+
+    /// Registers a component whose fields can be listed, read and written by
+    /// path through the `Reflect` trait.
+    pub fn register_reflect_component<T: IComponent + Reflect>(&mut self) {
+        self.update_info::<T>(ComponentInfo::set_reflect::<T>);
+    }
+
+    /// Registers a component type that has no Rust counterpart, e.g. one
+    /// defined by a script. Its values are stored as raw bytes of `layout`.
+    pub fn register_dynamic_component(
+        &mut self,
+        name: &str,
+        layout: Layout,
+        drop_fn: Option<DynamicDropFn>,
+    ) -> ComponentType {
+        let component_type = self
+            .registry
+            .write()
+            .unwrap()
+            .register_dynamic(name, layout, drop_fn)
+            .component_type;
+        self.dynamic_array_mut(component_type);
+        component_type
+    }
+
+    pub fn add_dynamic_component(
+        &mut self,
+        component_type: ComponentType,
+        entity: EntityType,
+        bytes: &[u8],
+    ) {
+        let mut guard = self.dynamic_array_mut(component_type).lock().unwrap();
+        guard.insert_data(entity, bytes);
+    }
+
+    pub fn remove_dynamic_component(&mut self, component_type: ComponentType, entity: EntityType) {
+        let mut guard = self.get_dynamic_array(component_type).lock().unwrap();
+        guard.remove_data(entity);
+    }
+
+    pub fn get_dynamic_component<F, R>(
+        &self,
+        component_type: ComponentType,
+        entity: EntityType,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let guard = self.get_dynamic_array(component_type).lock().unwrap();
+        f(guard
+            .get_data(entity)
+            .expect("Retrieving non-existent component."))
+    }
+
+    pub fn get_dynamic_component_mut<F, R>(
+        &self,
+        component_type: ComponentType,
+        entity: EntityType,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut guard = self.get_dynamic_array(component_type).lock().unwrap();
+        f(guard
+            .get_data_mut(entity)
+            .expect("Retrieving non-existent component."))
+    }
 
-        self.next_component_type += 1;
+    fn get_dynamic_array(
+        &self,
+        component_type: ComponentType,
+    ) -> &Arc<Mutex<DynamicComponentArray>> {
+        self.dynamic_arrays
+            .get(&component_type)
+            .expect("Dynamic component array not found")
+    }
+
+    fn dynamic_array_mut(
+        &mut self,
+        component_type: ComponentType,
+    ) -> &Arc<Mutex<DynamicComponentArray>> {
+        let registry = &self.registry;
+        self.dynamic_arrays
+            .entry(component_type)
+            .or_insert_with(|| {
+                let registry = registry.read().unwrap();
+                let info = registry
+                    .get_by_component_type(component_type)
+                    .filter(|info| info.is_dynamic())
+                    .expect("Dynamic component type not registered");
+                let layout = Layout::from_size_align(info.size, info.align).unwrap();
+                Arc::new(Mutex::new(DynamicComponentArray::new(layout, info.drop_fn)))
+            })
+    }
+
+    /// Registers a component that `EcsSingleton::clone_entity` may copy.
+    pub fn register_cloneable_component<T: IComponent + Clone>(&mut self) {
+        self.update_info::<T>(ComponentInfo::set_cloneable::<T>);
+    }
+
+    /// Calls `T::entity_destroyed` on a component right before its entity is
+    /// destroyed.
+    pub fn register_destroy_hook<T: IComponent>(&mut self) {
+        self.update_info::<T>(ComponentInfo::set_destroy_hook::<T>);
+    }
+
+    /// Copies one component of `entity`. Returns `None` when the type has no
+    /// cloner, or is a dynamic component with a drop function, since its
+    /// bytes may own resources that can't be duplicated bitwise.
+    pub fn clone_component(
+        &self,
+        info: &ComponentInfo,
+        entity: EntityType,
+    ) -> Option<DetachedComponent> {
+        if info.tag {
+            return Some(DetachedComponent::Tag(info.component_type));
+        }
+        match info.type_id {
+            Some(type_id) => {
+                let cloner = info.cloner?;
+                let guard = self.component_arrays.get(&type_id)?.read().unwrap();
+                Some(DetachedComponent::Typed(
+                    type_id,
+                    cloner(guard.get_any(entity)?),
+                ))
+            }
+            None => {
+                let guard = self
+                    .dynamic_arrays
+                    .get(&info.component_type)?
+                    .lock()
+                    .unwrap();
+                if guard.has_drop_fn() {
+                    return None;
+                }
+                Some(DetachedComponent::Dynamic(
+                    info.component_type,
+                    guard.get_data(entity)?.to_vec(),
+                ))
+            }
+        }
+    }
+
+    /// Removes one component of `entity` from storage without dropping it,
+    /// so it can be attached elsewhere.
+    pub fn take_component(
+        &mut self,
+        info: &ComponentInfo,
+        entity: EntityType,
+    ) -> Option<DetachedComponent> {
+        if info.tag {
+            return Some(DetachedComponent::Tag(info.component_type));
+        }
+        match info.type_id {
+            Some(type_id) => {
+                let mut guard = self.component_arrays.get(&type_id)?.write().unwrap();
+                Some(DetachedComponent::Typed(type_id, guard.take_boxed(entity)?))
+            }
+            None => {
+                let mut guard = self
+                    .dynamic_arrays
+                    .get(&info.component_type)?
+                    .lock()
+                    .unwrap();
+                Some(DetachedComponent::Dynamic(
+                    info.component_type,
+                    guard.take_data(entity)?,
+                ))
+            }
+        }
+    }
+
+    fn update_info<T: Any>(&mut self, f: impl FnOnce(&mut ComponentInfo)) {
+        let type_id = TypeId::of::<T>();
+        if !self.registry().contains(type_id) {
+            self.register_component::<T>();
+        }
+        f(self.registry.write().unwrap().get_mut(type_id).unwrap());
+    }
+
+    pub fn registry(&self) -> RwLockReadGuard<'_, ComponentRegistry> {
+        self.registry.read().unwrap()
+    }
+
+    /// A handle to the registry, for creating worlds whose `ComponentType`
+    /// ids agree with this one.
+    pub fn shared_registry(&self) -> SharedRegistry {
+        Arc::clone(&self.registry)
+    }
+
+    pub fn get_component_type_by_id(&self, type_id: TypeId) -> ComponentType {
+        self.registry()
+            .get(type_id)
+            .expect("Component type not registered")
+            .component_type
+    }
+
+    /// Runs `f` on the read-only reflected view of one component of
+    /// `entity`.
+    pub fn with_reflect<F, R>(&self, type_id: TypeId, entity: EntityType, f: F) -> Option<R>
+    where
+        F: FnOnce(&dyn Reflect) -> R,
+    {
+        let vtable = self.registry().get(type_id)?.reflect?;
+        let guard = self.component_arrays.get(&type_id)?.read().unwrap();
+        let component = guard.get_any(entity)?;
+        Some(f((vtable.as_reflect)(component)))
+    }
+
+    /// Runs `f` on the reflected view of one component of `entity`.
+    pub fn with_reflect_mut<F, R>(&mut self, type_id: TypeId, entity: EntityType, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut dyn Reflect) -> R,
+    {
+        let vtable = self.registry().get(type_id)?.reflect?;
+        let mut guard = self.component_arrays.get(&type_id)?.write().unwrap();
+        let component = guard.get_any_mut(entity)?;
+        Some(f((vtable.as_reflect_mut)(component)))
+    }
+
+    pub fn add_component_boxed(
+        &mut self,
+        type_id: TypeId,
+        entity: EntityType,
+        component: BoxedComponent,
+    ) {
+        let mut guard = self.array_mut(type_id).write().unwrap();
+        guard.insert_boxed(entity, component);
+    }
+
+    /// Inserts one component per entity, locking the array once. Tags have
+    /// no storage, so their values are simply dropped.
+    pub fn add_component_batch<T: Any + IComponent>(
+        &mut self,
+        entities: &[EntityType],
+        components: Vec<T>,
+    ) {
+        self.register_on_first_use::<T>();
+        if self.is_tag(TypeId::of::<T>()) {
+            return;
+        }
+        let mut guard = self.array_mut(TypeId::of::<T>()).write().unwrap();
+        for (entity, component) in entities.iter().zip(components) {
+            guard.insert_data(*entity, component);
+        }
     }
 
     pub fn add_component<T: Any + IComponent>(&mut self, entity: EntityType, component: T) {
-        let mut guard = self.get_component_array::<T>().lock().unwrap();
+        self.register_on_first_use::<T>();
+        let mut guard = self.array_mut(TypeId::of::<T>()).write().unwrap();
         guard.insert_data(entity, component);
     }
     pub fn remove_component<T: Any + IComponent>(&mut self, entity: EntityType) {
@@ -48,7 +340,7 @@ impl ComponentManager {
     }
 
     fn get_component_internal_remove_data<T: Any + IComponent>(&self, entity: EntityType) {
-        let mut guard = self.get_component_array::<T>().lock().unwrap();
+        let mut guard = self.get_component_array::<T>().write().unwrap();
         guard.remove_data(entity);
     }
 
@@ -56,9 +348,8 @@ impl ComponentManager {
         self.get_component_internal_get_data::<T>(entity)
     }
 
-    pub fn get_component_type<T: Any>(&self) -> &u32 {
-        let type_id = TypeId::of::<T>();
-        self.component_types.get(&type_id).unwrap()
+    pub fn get_component_type<T: Any>(&self) -> ComponentType {
+        self.get_component_type_by_id(TypeId::of::<T>())
     }
 
     pub fn get_component_mut<T: Any + IComponent, F, R>(&self, entity: EntityType, f: F) -> R
@@ -71,30 +362,156 @@ impl ComponentManager {
                 .get(&TypeId::of::<T>())
                 .unwrap(),
         );
-        let mut guard = val.lock().unwrap();
+        let mut guard = val.write().unwrap();
         let component = guard.get_data_mut::<T>(entity);
 
         f(component)
     }
 
     fn get_component_internal_get_data<T: Any + IComponent>(&self, entity: EntityType) -> &T {
-        let mut guard = self.get_component_array::<T>().lock().unwrap();
+        let guard = self.get_component_array::<T>().read().unwrap();
         let data = guard.get_data::<T>(entity);
 
         unsafe { &*(data as *const _) }
     }
 
-    fn get_component_array<T: Any + IComponent>(&self) -> &Arc<Mutex<ComponentArray>> {
+    fn get_component_array<T: Any + IComponent>(&self) -> &Arc<RwLock<ComponentArray>> {
         self.component_arrays
             .get(&TypeId::of::<T>())
             .expect("Component array not found")
     }
 
+    pub(crate) fn component_array(&self, type_id: TypeId) -> &Arc<RwLock<ComponentArray>> {
+        self.component_arrays
+            .get(&type_id)
+            .expect("Component array not found")
+    }
+
+    pub(crate) fn ensure_array<T: IComponent>(&mut self) {
+        self.register_on_first_use::<T>();
+        self.array_mut(TypeId::of::<T>());
+    }
+
+    /// Registers `T` if it isn't yet and auto-registration is on, with the
+    /// metadata from `IComponent::init_info`; without auto-registration,
+    /// using an unregistered type panics here, naming the type.
+    pub(crate) fn register_on_first_use<T: IComponent>(&mut self) {
+        self.register_on_first_use_with::<T>(T::init_info);
+    }
+
+    /// Like `register_on_first_use`, for types that may not implement
+    /// `IComponent` and so are registered without metadata.
+    pub(crate) fn register_type_on_first_use<T: Any>(&mut self) {
+        self.register_on_first_use_with::<T>(|_| {});
+    }
+
+    /// Like `register_on_first_use`, registering `T` as a tag.
+    pub(crate) fn register_tag_on_first_use<T: Any>(&mut self) {
+        self.register_on_first_use_with::<T>(|info| {
+            assert!(info.size == 0, "Tag components must be zero-sized.");
+            info.tag = true;
+        });
+    }
+
+    /// The check and the registration happen under one write lock, so two
+    /// worlds sharing the registry can't both register `T`.
+    fn register_on_first_use_with<T: Any>(&mut self, init: impl FnOnce(&mut ComponentInfo)) {
+        let type_id = TypeId::of::<T>();
+        if self.registry().contains(type_id) {
+            return;
+        }
+        let mut registry = self.registry.write().unwrap();
+        if registry.contains(type_id) {
+            return;
+        }
+        assert!(
+            registry.auto_register(),
+            "Component {} is not registered; register it first or enable auto-registration.",
+            type_name::<T>()
+        );
+        init(registry.register::<T>());
+    }
+
+    /// Freezes the registry, see `ComponentRegistry::freeze`, and moves
+    /// dynamic arrays to their new ids. Returns the new id of every old one.
+    pub fn freeze_registry(&mut self) -> Result<Vec<ComponentType>, FreezeError> {
+        if Arc::strong_count(&self.registry) > 1 {
+            return Err(FreezeError::SharedRegistry);
+        }
+        let remap = self.registry.write().unwrap().freeze();
+        self.dynamic_arrays = mem::take(&mut self.dynamic_arrays)
+            .into_iter()
+            .map(|(component_type, array)| (remap[component_type as usize], array))
+            .collect();
+        Ok(remap)
+    }
+
+    /// The array for a registered type, created on first use when the type
+    /// was registered through another world sharing the registry.
+    fn array_mut(&mut self, type_id: TypeId) -> &Arc<RwLock<ComponentArray>> {
+        assert!(
+            self.registry().contains(type_id),
+            "Component array not found"
+        );
+        self.component_arrays
+            .entry(type_id)
+            .or_insert_with(|| Arc::new(RwLock::new(ComponentArray::new())))
+    }
+
+    /// Every registered component that has storage in this world, with the
+    /// problems found in it and the entities it holds.
+    pub fn check_storage(&self) -> Vec<StorageReport> {
+        self.registry()
+            .iter()
+            .filter(|info| !info.tag)
+            .filter_map(|info| {
+                let (problems, entities) = match info.type_id {
+                    Some(type_id) => {
+                        let array = self.component_arrays.get(&type_id)?.read().unwrap();
+                        (array.check(), array.entities().collect())
+                    }
+                    None => {
+                        let array = self
+                            .dynamic_arrays
+                            .get(&info.component_type)?
+                            .lock()
+                            .unwrap();
+                        (array.check(), array.entities().collect())
+                    }
+                };
+                Some(StorageReport {
+                    info: info.clone(),
+                    problems,
+                    entities,
+                })
+            })
+            .collect()
+    }
+
     pub fn entity_destroyed(&mut self, entity: EntityType) {
-        for component_array in self.component_arrays.iter_mut() {
-            let (_, components) = component_array;
+        self.entities_destroyed(&[entity]);
+    }
+
+    pub fn entities_destroyed(&mut self, entities: &[EntityType]) {
+        let registry = self.registry();
+        for (type_id, components) in self.component_arrays.iter() {
+            let on_destroy = registry.get(*type_id).and_then(|info| info.on_destroy);
+            if let Ok(mut component) = components.write() {
+                for entity in entities {
+                    if let (Some(on_destroy), Some(data)) =
+                        (on_destroy, component.get_any_mut(*entity))
+                    {
+                        on_destroy(data, *entity);
+                    }
+                    component.entity_destroyed(*entity)
+                }
+            }
+        }
+        for components in self.dynamic_arrays.values() {
             if let Ok(mut component) = components.lock() {
-                component.entity_destroyed(entity)
+                for entity in entities {
+                    component.entity_destroyed(*entity)
+                }
             }
         }
     }
@@ -103,6 +520,7 @@ impl ComponentManager {
 #[cfg(test)]
 mod component_manager {
     use super::*;
+    use crate::ecs::ecs::reflect::{ReflectError, ReflectValue};
 
     #[derive(Debug)]
     struct TestComponent {
@@ -113,14 +531,14 @@ mod component_manager {
         fn entity_destroyed(&mut self, _entity: EntityType) {}
     }
 
+    crate::impl_reflect_struct!(TestComponent { data });
+
     #[test]
     fn test_register_component() {
         let mut manager = ComponentManager::new();
         manager.register_component::<TestComponent>();
 
-        assert!(manager
-            .component_types
-            .contains_key(&TypeId::of::<TestComponent>()));
+        assert!(manager.registry().contains(TypeId::of::<TestComponent>()));
         assert!(manager
             .component_arrays
             .contains_key(&TypeId::of::<TestComponent>()));
@@ -138,15 +556,55 @@ mod component_manager {
 
         let opt_shareable_array = manager.get_component_array::<TestComponent>();
         let mut numbers_of_entries = 0;
-        if let Ok(mut shareable_array) = opt_shareable_array.lock() {
-            for correct in shareable_array.get_components().iter() {
-                if let Some(_) = correct {
-                    numbers_of_entries += 1;
-                }
+        if let Ok(shareable_array) = opt_shareable_array.read() {
+            for _ in shareable_array.get_components().iter().flatten() {
+                numbers_of_entries += 1;
             }
         } else {
-            assert!(false);
+            panic!("Component array lock poisoned");
         }
         assert_eq!(numbers_of_entries, 1);
     }
+
+    #[test]
+    fn test_registry_records_metadata() {
+        let mut manager = ComponentManager::new();
+        manager.register_reflect_component::<TestComponent>();
+
+        let registry = manager.registry();
+        let info = registry.get_by_name("TestComponent").unwrap();
+        assert_eq!(info.type_id, Some(TypeId::of::<TestComponent>()));
+        assert_eq!(info.component_type, 0);
+        assert_eq!(info.size, std::mem::size_of::<TestComponent>());
+        assert_eq!(info.align, std::mem::align_of::<TestComponent>());
+        assert!(info.reflect.is_some());
+        assert!(info.loader.is_none());
+    }
+
+    #[test]
+    fn test_reflect_get_and_set_by_path() {
+        let mut manager = ComponentManager::new();
+        manager.register_reflect_component::<TestComponent>();
+        let entity: EntityType = 3;
+        manager.add_component(entity, TestComponent { data: 42 });
+
+        let type_id = TypeId::of::<TestComponent>();
+        let names = manager.with_reflect(type_id, entity, |r| r.field_names());
+        assert_eq!(names, Some(vec!["data"]));
+
+        manager
+            .with_reflect_mut(type_id, entity, |r| {
+                r.set_path("data", ReflectValue::Int(7))
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(manager.get_component::<TestComponent>(entity).data, 7);
+
+        let debug = manager.with_reflect(type_id, entity, |r| format!("{:?}", r));
+        assert_eq!(debug.as_deref(), Some("TestComponent { data: 7 }"));
+        assert_eq!(
+            manager.with_reflect(type_id, entity, |r| r.get_path("missing")),
+            Some(Err(ReflectError::UnknownField("missing".to_string())))
+        );
+    }
 }
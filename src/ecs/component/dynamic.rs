@@ -0,0 +1,204 @@
+use std::{
+    alloc::{self, Layout},
+    collections::HashMap,
+    ptr::{self, NonNull},
+};
+
+use crate::ecs::ecs::config::{EntityType, MAX_ENTITIES};
+
+use super::component::{check_index_maps, StorageProblem};
+
+/// Called on the bytes of a dynamic component right before they are
+/// discarded, so scripts can release whatever the value owns.
+pub type DynamicDropFn = fn(&mut [u8]);
+
+/// Dense, untyped storage for a component type that only exists at runtime.
+/// Mirrors `ComponentArray` but packs values as raw bytes of `layout`.
+#[derive(Debug)]
+pub(crate) struct DynamicComponentArray {
+    layout: Layout,
+    stride: usize,
+    drop_fn: Option<DynamicDropFn>,
+    data: NonNull<u8>,
+    capacity: usize,
+    entity_to_index_map: HashMap<EntityType, usize>,
+    index_to_entity_map: HashMap<usize, EntityType>,
+    size: usize,
+}
+
+// SAFETY: the array exclusively owns its allocation and only hands out
+// borrows tied to `&self`/`&mut self`, like a `Vec<u8>` would.
+unsafe impl Send for DynamicComponentArray {}
+unsafe impl Sync for DynamicComponentArray {}
+
+impl DynamicComponentArray {
+    pub fn new(layout: Layout, drop_fn: Option<DynamicDropFn>) -> Self {
+        let stride = layout.pad_to_align().size();
+        Self {
+            layout,
+            stride,
+            drop_fn,
+            data: dangling(layout),
+            capacity: if stride == 0 { usize::MAX } else { 0 },
+            entity_to_index_map: HashMap::new(),
+            index_to_entity_map: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    pub fn has_drop_fn(&self) -> bool {
+        self.drop_fn.is_some()
+    }
+
+    pub fn contains(&self, entity: EntityType) -> bool {
+        self.entity_to_index_map.contains_key(&entity)
+    }
+
+    pub fn insert_data(&mut self, entity: EntityType, bytes: &[u8]) {
+        assert_eq!(
+            bytes.len(),
+            self.layout.size(),
+            "Dynamic component data does not match its layout."
+        );
+        assert!(
+            !self.entity_to_index_map.contains_key(&entity),
+            "Data ids added to the same id more than once."
+        );
+        assert!(
+            (self.size as u32) < MAX_ENTITIES,
+            "To many ids in this DataContainer"
+        );
+
+        if self.size == self.capacity {
+            self.grow();
+        }
+        let new_index = self.size;
+        unsafe {
+            ptr::copy_nonoverlapping(bytes.as_ptr(), self.slot(new_index), bytes.len());
+        }
+        self.entity_to_index_map.insert(entity, new_index);
+        self.index_to_entity_map.insert(new_index, entity);
+        self.size += 1;
+    }
+
+    pub fn get_data(&self, entity: EntityType) -> Option<&[u8]> {
+        let index = *self.entity_to_index_map.get(&entity)?;
+        Some(unsafe { std::slice::from_raw_parts(self.slot(index), self.layout.size()) })
+    }
+
+    pub fn get_data_mut(&mut self, entity: EntityType) -> Option<&mut [u8]> {
+        let index = *self.entity_to_index_map.get(&entity)?;
+        Some(unsafe { std::slice::from_raw_parts_mut(self.slot(index), self.layout.size()) })
+    }
+
+    /// Removes the bytes of `entity` without running the drop function, so
+    /// ownership of whatever they refer to moves to the caller.
+    pub fn take_data(&mut self, entity: EntityType) -> Option<Vec<u8>> {
+        let bytes = self.get_data(entity)?.to_vec();
+        self.remove_slot(entity);
+        Some(bytes)
+    }
+
+    pub fn remove_data(&mut self, entity: EntityType) {
+        assert!(
+            self.entity_to_index_map.contains_key(&entity),
+            "Removing non-existent component."
+        );
+        self.drop_slot(self.entity_to_index_map[&entity]);
+        self.remove_slot(entity);
+    }
+
+    fn remove_slot(&mut self, entity: EntityType) {
+        let index_of_removed_entity = self.entity_to_index_map[&entity];
+        let index_of_last_element = self.size - 1;
+
+        if index_of_removed_entity != index_of_last_element {
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    self.slot(index_of_last_element),
+                    self.slot(index_of_removed_entity),
+                    self.stride,
+                );
+            }
+        }
+
+        let entity_of_last_element = self.index_to_entity_map[&index_of_last_element];
+        self.entity_to_index_map
+            .insert(entity_of_last_element, index_of_removed_entity);
+        self.index_to_entity_map
+            .insert(index_of_removed_entity, entity_of_last_element);
+
+        self.entity_to_index_map.remove(&entity);
+        self.index_to_entity_map.remove(&index_of_last_element);
+
+        self.size -= 1;
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = EntityType> + '_ {
+        self.entity_to_index_map.keys().copied()
+    }
+
+    pub fn check(&self) -> Vec<StorageProblem> {
+        check_index_maps(
+            &self.entity_to_index_map,
+            &self.index_to_entity_map,
+            self.size,
+        )
+    }
+
+    pub fn entity_destroyed(&mut self, entity: EntityType) {
+        if self.contains(entity) {
+            self.remove_data(entity);
+        }
+    }
+
+    fn slot(&self, index: usize) -> *mut u8 {
+        unsafe { self.data.as_ptr().add(index * self.stride) }
+    }
+
+    fn drop_slot(&mut self, index: usize) {
+        if let Some(drop_fn) = self.drop_fn {
+            let bytes =
+                unsafe { std::slice::from_raw_parts_mut(self.slot(index), self.layout.size()) };
+            drop_fn(bytes);
+        }
+    }
+
+    fn grow(&mut self) {
+        let new_capacity = (self.capacity * 2).max(4);
+        let new_layout = self.array_layout(new_capacity);
+        let data = unsafe {
+            if self.capacity == 0 {
+                alloc::alloc(new_layout)
+            } else {
+                alloc::realloc(
+                    self.data.as_ptr(),
+                    self.array_layout(self.capacity),
+                    new_layout.size(),
+                )
+            }
+        };
+        self.data = NonNull::new(data).unwrap_or_else(|| alloc::handle_alloc_error(new_layout));
+        self.capacity = new_capacity;
+    }
+
+    fn array_layout(&self, capacity: usize) -> Layout {
+        Layout::from_size_align(self.stride * capacity, self.layout.align())
+            .expect("Dynamic component storage too large")
+    }
+}
+
+impl Drop for DynamicComponentArray {
+    fn drop(&mut self) {
+        for index in 0..self.size {
+            self.drop_slot(index);
+        }
+        if self.stride != 0 && self.capacity != 0 {
+            unsafe { alloc::dealloc(self.data.as_ptr(), self.array_layout(self.capacity)) };
+        }
+    }
+}
+
+fn dangling(layout: Layout) -> NonNull<u8> {
+    NonNull::new(layout.align() as *mut u8).unwrap()
+}
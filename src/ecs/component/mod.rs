@@ -1,2 +1,6 @@
+pub mod bundle;
+pub mod clone;
 pub mod component;
 pub mod component_manager;
+pub mod dynamic;
+pub mod registry;
@@ -0,0 +1,353 @@
+use std::{
+    alloc::Layout,
+    any::{Any, TypeId},
+    collections::HashMap,
+    mem,
+    sync::{Arc, RwLock},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::ecs::ecs::{
+    clone::ComponentCloner,
+    component::{BoxedComponent, IComponent},
+    config::{ComponentType, EntityType, Signature},
+    dynamic::DynamicDropFn,
+    reflect::Reflect,
+};
+
+/// Why `EcsSingleton::freeze_components` left the registry as it was.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FreezeError {
+    /// Other worlds or `SharedRegistry` handles use the registry, and their
+    /// signatures would keep the old ids.
+    SharedRegistry,
+}
+
+/// Builds a boxed component from its data representation, used when
+/// components are described by name in scene files.
+pub type ComponentLoader = fn(serde_json::Value) -> Result<BoxedComponent, serde_json::Error>;
+
+/// Calls a stored component's `IComponent::entity_destroyed` before the
+/// component is dropped with its entity.
+pub type DestroyHook = fn(&mut dyn Any, EntityType);
+
+/// Casts type-erased storage back to `dyn Reflect` for a registered type.
+#[derive(Debug, Clone, Copy)]
+pub struct ReflectVTable {
+    pub as_reflect: fn(&dyn Any) -> &dyn Reflect,
+    pub as_reflect_mut: fn(&mut dyn Any) -> &mut dyn Reflect,
+}
+
+impl ReflectVTable {
+    pub fn of<T: Reflect>() -> Self {
+        Self {
+            as_reflect: |any| any.downcast_ref::<T>().expect("Reflect type mismatch"),
+            as_reflect_mut: |any| any.downcast_mut::<T>().expect("Reflect type mismatch"),
+        }
+    }
+}
+
+/// Everything the world knows about a registered component type.
+#[derive(Debug, Clone)]
+pub struct ComponentInfo {
+    pub name: String,
+    pub type_name: &'static str,
+    /// `None` for dynamic components, which only exist at runtime.
+    pub type_id: Option<TypeId>,
+    pub component_type: ComponentType,
+    pub size: usize,
+    pub align: usize,
+    pub loader: Option<ComponentLoader>,
+    pub reflect: Option<ReflectVTable>,
+    pub cloner: Option<ComponentCloner>,
+    /// Tags are zero-sized markers that live only in the entity signature.
+    pub tag: bool,
+    pub drop_fn: Option<DynamicDropFn>,
+    pub on_destroy: Option<DestroyHook>,
+}
+
+impl ComponentInfo {
+    pub fn is_dynamic(&self) -> bool {
+        self.type_id.is_none()
+    }
+
+    /// Lets `EcsSingleton::clone_entity` copy components of type `T`.
+    pub fn set_cloneable<T: IComponent + Clone>(&mut self) {
+        self.cloner = Some(|any| {
+            Box::new(
+                any.downcast_ref::<T>()
+                    .expect("Clone type mismatch")
+                    .clone(),
+            )
+        });
+    }
+
+    /// Lets scenes build components of type `T` from their data.
+    pub fn set_scene<T: IComponent + DeserializeOwned>(&mut self) {
+        self.loader =
+            Some(|value| serde_json::from_value::<T>(value).map(|c| Box::new(c) as BoxedComponent));
+    }
+
+    pub fn set_reflect<T: IComponent + Reflect>(&mut self) {
+        self.reflect = Some(ReflectVTable::of::<T>());
+    }
+
+    /// Calls `T::entity_destroyed` on a component right before its entity
+    /// is destroyed.
+    pub fn set_destroy_hook<T: IComponent>(&mut self) {
+        self.on_destroy = Some(|any, entity| {
+            any.downcast_mut::<T>()
+                .expect("Destroy hook type mismatch")
+                .entity_destroyed(entity)
+        });
+    }
+}
+
+/// A registry shared by several worlds so their `ComponentType` ids and
+/// signatures agree.
+pub type SharedRegistry = Arc<RwLock<ComponentRegistry>>;
+
+/// Maps component types to their numeric `ComponentType` and metadata.
+#[derive(Debug, Default)]
+pub struct ComponentRegistry {
+    infos: Vec<ComponentInfo>,
+    type_ids: HashMap<TypeId, ComponentType>,
+    names: HashMap<String, ComponentType>,
+    auto_register: bool,
+    /// New id of every type registered before `freeze`, indexed by old id.
+    freeze_map: Option<Vec<ComponentType>>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T: Any>(&mut self) -> &mut ComponentInfo {
+        let type_id = TypeId::of::<T>();
+        assert!(
+            !self.type_ids.contains_key(&type_id),
+            "Registering component type more than once."
+        );
+        self.assert_not_frozen(std::any::type_name::<T>());
+        let name = short_type_name(std::any::type_name::<T>());
+        assert!(
+            !self.names.contains_key(&name),
+            "Component name {} of {} is already registered.",
+            name,
+            std::any::type_name::<T>()
+        );
+
+        let component_type = self.push(ComponentInfo {
+            name,
+            type_name: std::any::type_name::<T>(),
+            type_id: Some(type_id),
+            component_type: 0,
+            size: mem::size_of::<T>(),
+            align: mem::align_of::<T>(),
+            loader: None,
+            reflect: None,
+            cloner: None,
+            tag: false,
+            drop_fn: None,
+            on_destroy: None,
+        });
+        self.type_ids.insert(type_id, component_type);
+        &mut self.infos[component_type as usize]
+    }
+
+    pub fn register_dynamic(
+        &mut self,
+        name: &str,
+        layout: Layout,
+        drop_fn: Option<DynamicDropFn>,
+    ) -> &mut ComponentInfo {
+        assert!(
+            !self.names.contains_key(name),
+            "Registering component name more than once."
+        );
+        self.assert_not_frozen(name);
+
+        let component_type = self.push(ComponentInfo {
+            name: name.to_string(),
+            type_name: "dynamic",
+            type_id: None,
+            component_type: 0,
+            size: layout.size(),
+            align: layout.align(),
+            loader: None,
+            reflect: None,
+            cloner: None,
+            tag: false,
+            drop_fn,
+            on_destroy: None,
+        });
+        &mut self.infos[component_type as usize]
+    }
+
+    fn push(&mut self, mut info: ComponentInfo) -> ComponentType {
+        let component_type = self.infos.len() as ComponentType;
+        info.component_type = component_type;
+        self.names.insert(info.name.clone(), component_type);
+        self.infos.push(info);
+        component_type
+    }
+
+    pub fn contains(&self, type_id: TypeId) -> bool {
+        self.type_ids.contains_key(&type_id)
+    }
+
+    pub fn get(&self, type_id: TypeId) -> Option<&ComponentInfo> {
+        self.type_ids
+            .get(&type_id)
+            .map(|component_type| &self.infos[*component_type as usize])
+    }
+
+    pub fn get_mut(&mut self, type_id: TypeId) -> Option<&mut ComponentInfo> {
+        self.type_ids
+            .get(&type_id)
+            .map(|component_type| &mut self.infos[*component_type as usize])
+    }
+
+    pub fn get_by_component_type(&self, component_type: ComponentType) -> Option<&ComponentInfo> {
+        self.infos.get(component_type as usize)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&ComponentInfo> {
+        self.names
+            .get(name)
+            .map(|component_type| &self.infos[*component_type as usize])
+    }
+
+    /// All registered components, ordered by `ComponentType`.
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
+        self.infos.iter()
+    }
+
+    /// Whether unknown component types are registered the first time they
+    /// are inserted or queried instead of panicking.
+    pub fn auto_register(&self) -> bool {
+        self.auto_register
+    }
+
+    pub fn set_auto_register(&mut self, enabled: bool) {
+        self.auto_register = enabled;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.freeze_map.is_some()
+    }
+
+    /// The renumbering done by `freeze`, indexed by the old id.
+    pub fn freeze_map(&self) -> Option<&[ComponentType]> {
+        self.freeze_map.as_deref()
+    }
+
+    /// Renumbers every registered type in type name order, dynamic
+    /// components by their name, so ids no longer depend on which type
+    /// happened to be used first. No types can be registered afterwards.
+    /// Returns the new id of every old one.
+    pub fn freeze(&mut self) -> Vec<ComponentType> {
+        assert!(!self.is_frozen(), "Component registry is already frozen.");
+
+        let mut order: Vec<usize> = (0..self.infos.len()).collect();
+        order.sort_by_key(|old| {
+            let info = &self.infos[*old];
+            if info.is_dynamic() {
+                info.name.as_str()
+            } else {
+                info.type_name
+            }
+        });
+        let mut remap = vec![0; self.infos.len()];
+        for (new, old) in order.iter().enumerate() {
+            remap[*old] = new as ComponentType;
+        }
+
+        let mut infos: Vec<Option<ComponentInfo>> =
+            mem::take(&mut self.infos).into_iter().map(Some).collect();
+        self.infos = order
+            .iter()
+            .map(|old| {
+                let mut info = infos[*old].take().unwrap();
+                info.component_type = remap[*old];
+                info
+            })
+            .collect();
+        for component_type in self.type_ids.values_mut().chain(self.names.values_mut()) {
+            *component_type = remap[*component_type as usize];
+        }
+
+        self.freeze_map = Some(remap.clone());
+        remap
+    }
+
+    fn assert_not_frozen(&self, name: &str) {
+        assert!(
+            !self.is_frozen(),
+            "Registering component {} after the registry was frozen.",
+            name
+        );
+    }
+}
+
+/// `signature` with every component type replaced by its entry in `remap`.
+pub fn remap_signature(signature: &Signature, remap: &[ComponentType]) -> Signature {
+    signature
+        .iter()
+        .map(|component_type| remap[component_type] as usize)
+        .collect()
+}
+
+/// `name` with every path shortened to its last segment, generic arguments
+/// included, e.g. `Relation<Targets>` for
+/// `singleton_ecs::relation::Relation<game::Targets>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    let mut path = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            path.push(c);
+        } else {
+            short.push_str(path.rsplit("::").next().unwrap_or(&path));
+            path.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(path.rsplit("::").next().unwrap_or(&path));
+    short
+}
+
+#[cfg(test)]
+mod registry {
+    use super::*;
+
+    #[test]
+    fn test_short_type_name_keeps_generic_arguments() {
+        assert_eq!(short_type_name("game::Transform"), "Transform");
+        assert_eq!(
+            short_type_name("singleton_ecs::relation::Relation<game::Targets>"),
+            "Relation<Targets>"
+        );
+        assert_eq!(
+            short_type_name("a::Pair<a::B, (u8, alloc::vec::Vec<c::D>)>"),
+            "Pair<B, (u8, Vec<D>)>"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Component name Transform of")]
+    fn test_register_rejects_duplicate_names() {
+        mod a {
+            pub struct Transform;
+        }
+        mod b {
+            pub struct Transform;
+        }
+
+        let mut registry = ComponentRegistry::new();
+        registry.register::<a::Transform>();
+        registry.register::<b::Transform>();
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::ecs::ecs::resource_manager::Resource;
+
+/// One run of one system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemSample {
+    pub name: &'static str,
+    /// Offset from the creation of the `Diagnostics` resource.
+    pub start: Duration,
+    pub duration: Duration,
+    /// Entities matched by the system's signature, or for a function system
+    /// by its queries, summed over the queries.
+    pub entities: usize,
+    /// Entities whose signature changed while the system ran, counting
+    /// commands applied after it.
+    pub structural_changes: usize,
+}
+
+/// One `EcsSingleton::update`, with every system run during it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrameSample {
+    pub tick: u64,
+    pub start: Duration,
+    pub duration: Duration,
+    pub systems: Vec<SystemSample>,
+}
+
+/// Averages of one system over the frames kept in the window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemStats {
+    pub name: &'static str,
+    pub runs: usize,
+    pub mean_time: Duration,
+    pub max_time: Duration,
+    pub mean_entities: f32,
+    pub mean_structural_changes: f32,
+}
+
+/// Per-system timings of the last `window` frames, kept as a resource by
+/// `EcsSingleton::enable_diagnostics` and filled in by the scheduler.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    epoch: Instant,
+    window: usize,
+    tick: u64,
+    frames: VecDeque<FrameSample>,
+    pending: Vec<SystemSample>,
+}
+
+impl Resource for Diagnostics {}
+
+impl Diagnostics {
+    pub fn new(window: usize) -> Self {
+        assert!(
+            window > 0,
+            "Diagnostics window must hold at least one frame."
+        );
+        Self {
+            epoch: Instant::now(),
+            window,
+            tick: 0,
+            frames: VecDeque::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record_system(
+        &mut self,
+        name: &'static str,
+        start: Instant,
+        duration: Duration,
+        entities: usize,
+        structural_changes: usize,
+    ) {
+        self.pending.push(SystemSample {
+            name,
+            start: start.saturating_duration_since(self.epoch),
+            duration,
+            entities,
+            structural_changes,
+        });
+    }
+
+    pub(crate) fn end_frame(&mut self, start: Instant, duration: Duration) {
+        self.tick += 1;
+        if self.frames.len() == self.window {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(FrameSample {
+            tick: self.tick,
+            start: start.saturating_duration_since(self.epoch),
+            duration,
+            systems: std::mem::take(&mut self.pending),
+        });
+    }
+
+    /// Frames in the window, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameSample> {
+        self.frames.iter()
+    }
+
+    pub fn last_frame(&self) -> Option<&FrameSample> {
+        self.frames.back()
+    }
+
+    pub fn mean_frame_time(&self) -> Duration {
+        if self.frames.is_empty() {
+            return Duration::ZERO;
+        }
+        let total: Duration = self.frames.iter().map(|frame| frame.duration).sum();
+        total / self.frames.len() as u32
+    }
+
+    /// Stats for every system seen in the window, in the order they first
+    /// ran.
+    pub fn systems(&self) -> Vec<SystemStats> {
+        let mut names: Vec<&'static str> = Vec::new();
+        for sample in self.samples() {
+            if !names.contains(&sample.name) {
+                names.push(sample.name);
+            }
+        }
+        names
+            .into_iter()
+            .filter_map(|name| self.system(name))
+            .collect()
+    }
+
+    pub fn system(&self, name: &str) -> Option<SystemStats> {
+        let samples: Vec<&SystemSample> = self
+            .samples()
+            .filter(|sample| sample.name == name)
+            .collect();
+        let first = samples.first()?;
+        let runs = samples.len();
+        let total: Duration = samples.iter().map(|sample| sample.duration).sum();
+        let entities: usize = samples.iter().map(|sample| sample.entities).sum();
+        let changes: usize = samples.iter().map(|sample| sample.structural_changes).sum();
+        Some(SystemStats {
+            name: first.name,
+            runs,
+            mean_time: total / runs as u32,
+            max_time: samples.iter().map(|s| s.duration).max().unwrap(),
+            mean_entities: entities as f32 / runs as f32,
+            mean_structural_changes: changes as f32 / runs as f32,
+        })
+    }
+
+    fn samples(&self) -> impl Iterator<Item = &SystemSample> {
+        self.frames.iter().flat_map(|frame| frame.systems.iter())
+    }
+
+    /// The window in Chrome's trace event format, one complete event per
+    /// frame and per system run. Open it in `chrome://tracing` or Perfetto.
+    pub fn to_chrome_trace(&self) -> Value {
+        let events: Vec<Value> = self.frames.iter().flat_map(trace_events).collect();
+        json!({ "traceEvents": events, "displayTimeUnit": "ms" })
+    }
+
+    pub fn write_chrome_trace(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_chrome_trace().to_string())
+    }
+}
+
+/// Writes a Chrome trace one frame at a time, so tracing a long run needs
+/// no window holding every frame. The output matches `to_chrome_trace`.
+pub struct ChromeTraceWriter<W: Write> {
+    out: W,
+    empty: bool,
+}
+
+impl<W: Write> ChromeTraceWriter<W> {
+    pub fn new(mut out: W) -> io::Result<Self> {
+        write!(out, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        Ok(Self { out, empty: true })
+    }
+
+    pub fn write_frame(&mut self, frame: &FrameSample) -> io::Result<()> {
+        for event in trace_events(frame) {
+            if !self.empty {
+                write!(self.out, ",")?;
+            }
+            self.empty = false;
+            write!(self.out, "{}", event)?;
+        }
+        Ok(())
+    }
+
+    /// Closes the trace and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        write!(self.out, "]}}")?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// One complete event for `frame` and one per system run in it.
+fn trace_events(frame: &FrameSample) -> Vec<Value> {
+    let mut events = vec![json!({
+        "name": "frame",
+        "cat": "frame",
+        "ph": "X",
+        "ts": micros(frame.start),
+        "dur": micros(frame.duration),
+        "pid": 1,
+        "tid": 1,
+        "args": { "tick": frame.tick },
+    })];
+    for system in frame.systems.iter() {
+        events.push(json!({
+            "name": system.name,
+            "cat": "system",
+            "ph": "X",
+            "ts": micros(system.start),
+            "dur": micros(system.duration),
+            "pid": 1,
+            "tid": 1,
+            "args": {
+                "tick": frame.tick,
+                "entities": system.entities,
+                "structural_changes": system.structural_changes,
+            },
+        }));
+    }
+    events
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+#[cfg(test)]
+mod diagnostics {
+    use super::*;
+    use crate::ecs::ecs::{
+        component::IComponent, config::EntityType, param::Commands, query::Query,
+        singleton::EcsSingleton,
+    };
+
+    struct Health(i32);
+
+    impl IComponent for Health {
+        fn entity_destroyed(&mut self, _entity: EntityType) {}
+    }
+
+    fn damage(mut query: Query<&mut Health>) {
+        query.for_each(|health| health.0 -= 1);
+    }
+
+    fn spawn(mut commands: Commands) {
+        commands.spawn((Health(3),));
+    }
+
+    #[test]
+    fn test_records_systems_per_frame() {
+        let mut singleton = EcsSingleton::new();
+        singleton.register_component::<Health>();
+        singleton.register_system_func(damage);
+        singleton.register_system_func(spawn);
+        singleton.enable_diagnostics(2);
+
+        for _ in 0..3 {
+            singleton.update();
+        }
+
+        let diagnostics = singleton.get_resource::<Diagnostics>().unwrap();
+        assert_eq!(diagnostics.frames().count(), 2);
+        assert_eq!(diagnostics.last_frame().unwrap().tick, 3);
+
+        let systems = diagnostics.systems();
+        assert_eq!(systems.len(), 2);
+        assert!(systems[0].name.ends_with("damage"));
+        assert_eq!(systems[0].runs, 2);
+        assert_eq!(systems[0].mean_entities, 1.5);
+        assert_eq!(systems[0].mean_structural_changes, 0.0);
+        assert_eq!(systems[1].mean_structural_changes, 1.0);
+        assert!(diagnostics.mean_frame_time() >= systems[1].mean_time);
+
+        let trace = diagnostics.to_chrome_trace();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 6);
+        assert_eq!(events[0]["name"], "frame");
+        assert_eq!(events[2]["args"]["structural_changes"], 1);
+
+        let mut writer = ChromeTraceWriter::new(Vec::new()).unwrap();
+        for frame in diagnostics.frames() {
+            writer.write_frame(frame).unwrap();
+        }
+        let streamed = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(streamed, trace.to_string());
+    }
+}
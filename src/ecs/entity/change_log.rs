@@ -0,0 +1,72 @@
+use crate::ecs::ecs::config::{EntityType, MAX_ENTITIES};
+
+/// How many changes are kept before the oldest half is dropped. Readers that
+/// fall further behind than this rebuild from scratch.
+const CHANGE_LOG_CAPACITY: usize = MAX_ENTITIES as usize * 4;
+
+/// Entities whose signature changed, in order. Positions are absolute, so a
+/// reader keeps a cursor and later asks for everything after it.
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+    start: usize,
+    entries: Vec<EntityType>,
+}
+
+impl ChangeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, entity: EntityType) {
+        if self.entries.len() == CHANGE_LOG_CAPACITY {
+            let dropped = CHANGE_LOG_CAPACITY / 2;
+            self.entries.drain(..dropped);
+            self.start += dropped;
+        }
+        self.entries.push(entity);
+    }
+
+    /// Cursor just past the newest change.
+    pub fn end(&self) -> usize {
+        self.start + self.entries.len()
+    }
+
+    /// Changes recorded since `cursor`, or `None` if some were already
+    /// dropped.
+    pub fn since(&self, cursor: usize) -> Option<&[EntityType]> {
+        let offset = cursor.checked_sub(self.start)?;
+        self.entries.get(offset..)
+    }
+}
+
+#[cfg(test)]
+mod change_log {
+    use super::*;
+
+    #[test]
+    fn test_since_cursor() {
+        let mut log = ChangeLog::new();
+        log.record(1);
+        let cursor = log.end();
+        log.record(2);
+        log.record(3);
+
+        assert_eq!(log.since(cursor), Some(&[2, 3][..]));
+        assert_eq!(log.since(log.end()), Some(&[][..]));
+    }
+
+    #[test]
+    fn test_dropped_changes_need_rebuild() {
+        let mut log = ChangeLog::new();
+        for entity in 0..=CHANGE_LOG_CAPACITY as EntityType {
+            log.record(entity);
+        }
+
+        assert!(log.since(0).is_none());
+        assert_eq!(log.end(), CHANGE_LOG_CAPACITY + 1);
+        assert_eq!(
+            log.since(log.end() - 1),
+            Some(&[CHANGE_LOG_CAPACITY as EntityType][..])
+        );
+    }
+}
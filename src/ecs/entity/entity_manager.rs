@@ -1,9 +1,14 @@
 use core::panic;
-use std::{collections::VecDeque, ops::IndexMut, usize};
+use std::{collections::VecDeque, ops::IndexMut};
 
-use crate::ecs::ecs::config::{EntityType, Signature, MAX_ENTITIES};
+use crate::ecs::ecs::{
+    config::{ComponentType, EntityType, Signature, MAX_ENTITIES},
+    registry::remap_signature,
+};
 
-#[derive(Debug)]
+use super::change_log::ChangeLog;
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum EntityManagerResponse {
     CantAddMoreEntities,
     AddedEntities,
@@ -14,8 +19,11 @@ pub enum EntityManagerResponse {
 #[derive(Debug)]
 pub struct EntityManager {
     entities: VecDeque<EntityType>,
+    /// `None` exactly for ids that are not alive. Only creating and
+    /// destroying an entity switches between the two.
     signatures: [Option<Signature>; 5000],
     living: u32,
+    changes: ChangeLog,
 }
 
 impl EntityManager {
@@ -29,29 +37,101 @@ impl EntityManager {
             entities: vec_dequeue,
             living: 0,
             signatures: array_init::array_init(|_| None),
+            changes: ChangeLog::new(),
         }
     }
     pub fn create_entity(&mut self) -> u32 {
         if let Some(id) = self.entities.pop_front() {
             self.living += 1;
+            self.signatures[id as usize] = Some(Signature::default());
+            self.changes.record(id);
             id
         } else {
             panic!("could not create entity")
         }
     }
 
+    /// Reserves `count` ids at once, each starting with an empty signature.
+    pub fn create_entities(&mut self, count: usize) -> Vec<EntityType> {
+        assert!(count <= self.entities.len(), "could not create entity");
+        let ids: Vec<EntityType> = self.entities.drain(..count).collect();
+        for id in ids.iter() {
+            self.signatures[*id as usize] = Some(Signature::default());
+            self.changes.record(*id);
+        }
+        self.living += count as u32;
+        ids
+    }
+
     pub fn set_signature(&mut self, entity: EntityType, signature: Signature) {
-        self.signatures[entity as usize] = Some(signature)
+        *self.get_signature(entity) = signature;
+        self.changes.record(entity);
     }
+
+    /// Clears the signature of `entity`, which stays alive.
     pub fn remove_signature(&mut self, entity: EntityType) {
-        self.signatures[entity as usize] = None;
+        self.set_signature(entity, Signature::default());
     }
 
-    pub fn destroy_entity(&mut self, entity: EntityType) {
+    /// Frees `entity` for reuse. Destroying an id that is not alive is
+    /// refused so it can't end up in the free list twice.
+    pub fn destroy_entity(&mut self, entity: EntityType) -> EntityManagerResponse {
+        if !self.is_alive(entity) {
+            return EntityManagerResponse::CantRemoveEntity;
+        }
         self.signatures[entity as usize] = None;
+        self.changes.record(entity);
         self.entities.push_back(entity);
+        self.living -= 1;
+        EntityManagerResponse::RemovedEntity
+    }
+
+    pub fn is_alive(&self, entity: EntityType) -> bool {
+        self.signature(entity).is_some()
+    }
+
+    /// Ids waiting to be handed out, in the order they will be reused.
+    pub fn free_entities(&self) -> impl Iterator<Item = EntityType> + '_ {
+        self.entities.iter().copied()
+    }
+
+    pub fn living(&self) -> u32 {
+        self.living
+    }
+
+    /// Live entities in id order, with their signatures.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityType, &Signature)> {
+        self.signatures
+            .iter()
+            .enumerate()
+            .filter_map(|(entity, signature)| Some((entity as EntityType, signature.as_ref()?)))
+    }
+
+    pub fn signature(&self, entity: EntityType) -> Option<&Signature> {
+        self.signatures.get(entity as usize)?.as_ref()
+    }
+
+    /// Rewrites every signature for renumbered component types. Nothing is
+    /// logged: which entities have which components is unchanged.
+    pub fn remap_signatures(&mut self, remap: &[ComponentType]) {
+        for signature in self.signatures.iter_mut().flatten() {
+            *signature = remap_signature(signature, remap);
+        }
+    }
+
+    /// Every structural change so far: creation, destruction and signature
+    /// edits.
+    pub fn changes(&self) -> &ChangeLog {
+        &self.changes
     }
 
+    /// Logs a signature edit made through `get_signature`.
+    pub fn record_change(&mut self, entity: EntityType) {
+        self.changes.record(entity);
+    }
+
+    /// Mutable signature of `entity`. Nothing is logged; callers that edit
+    /// it follow up with `record_change`.
     pub fn get_signature(&mut self, entity: EntityType) -> &mut Signature {
         if let Some(signature) = self.signatures.index_mut(entity as usize) {
             return signature;
@@ -59,3 +139,22 @@ impl EntityManager {
         panic!("tryig to access a entity signature from non existing");
     }
 }
+
+#[cfg(test)]
+mod entity_manager {
+    use super::*;
+
+    #[test]
+    fn test_removing_a_signature_keeps_the_entity() {
+        let mut manager = EntityManager::create();
+        let entity = manager.create_entity();
+        let mut signature = Signature::default();
+        signature.insert(3);
+        manager.set_signature(entity, signature);
+
+        manager.remove_signature(entity);
+        assert!(manager.is_alive(entity));
+        assert_eq!(manager.signature(entity), Some(&Signature::default()));
+        assert_eq!(manager.living(), 1);
+    }
+}
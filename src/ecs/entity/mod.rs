@@ -1 +1,2 @@
+pub mod change_log;
 pub mod entity_manager;
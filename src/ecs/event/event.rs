@@ -0,0 +1,147 @@
+use std::any::Any;
+
+use crate::ecs::ecs::{
+    access::Access,
+    param::{return_resource, take_resource, SystemParam},
+    resource_manager::Resource,
+    singleton::EcsSingleton,
+};
+
+/// Messages of type `E`, stored as a resource by `EcsSingleton::add_event`.
+///
+/// Events stay readable for two `update`s, so every system sees an event
+/// sent during the previous tick no matter where it runs in the order.
+#[derive(Debug)]
+pub struct Events<E> {
+    events: Vec<(usize, E)>,
+    next_id: usize,
+    update_start: usize,
+}
+
+impl<E: Any + Send + Sync> Resource for Events<E> {}
+
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Self {
+            events: Vec::new(),
+            next_id: 0,
+            update_start: 0,
+        }
+    }
+}
+
+impl<E> Events<E> {
+    pub fn send(&mut self, event: E) {
+        self.events.push((self.next_id, event));
+        self.next_id += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Drops the events sent before the previous update.
+    pub fn update(&mut self) {
+        let start = self.update_start;
+        self.events.retain(|(id, _)| *id >= start);
+        self.update_start = self.next_id;
+    }
+
+    fn read_from(&self, cursor: usize) -> impl Iterator<Item = &E> {
+        self.events
+            .iter()
+            .filter(move |(id, _)| *id >= cursor)
+            .map(|(_, event)| event)
+    }
+}
+
+pub(crate) fn update_events<E: Any + Send + Sync>(singleton: &mut EcsSingleton) {
+    if let Some(events) = singleton.get_resource_mut::<Events<E>>() {
+        events.update();
+    }
+}
+
+/// Reads the events a system hasn't seen yet.
+pub struct EventReader<'w, E> {
+    events: &'w Events<E>,
+    cursor: &'w mut usize,
+}
+
+impl<'w, E> EventReader<'w, E> {
+    pub fn read(&mut self) -> impl Iterator<Item = &'w E> {
+        let cursor = *self.cursor;
+        *self.cursor = self.events.next_id;
+        self.events.read_from(cursor)
+    }
+}
+
+pub struct EventReaderState<E> {
+    events: Option<Events<E>>,
+    cursor: usize,
+}
+
+impl<E: Any + Send + Sync> SystemParam for EventReader<'_, E> {
+    type State = EventReaderState<E>;
+    type Item<'w> = EventReader<'w, E>;
+
+    fn init(_singleton: &mut EcsSingleton, access: &mut Access) -> Self::State {
+        access.add_resource_read::<Events<E>>();
+        EventReaderState {
+            events: None,
+            cursor: 0,
+        }
+    }
+
+    fn prepare(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        take_resource(&mut state.events, singleton);
+    }
+
+    fn fetch<'w>(state: &'w mut Self::State, _singleton: &'w EcsSingleton) -> Self::Item<'w> {
+        EventReader {
+            events: state.events.as_ref().unwrap(),
+            cursor: &mut state.cursor,
+        }
+    }
+
+    fn apply(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        return_resource(&mut state.events, singleton);
+    }
+}
+
+pub struct EventWriter<'w, E> {
+    events: &'w mut Events<E>,
+}
+
+impl<E> EventWriter<'_, E> {
+    pub fn send(&mut self, event: E) {
+        self.events.send(event);
+    }
+}
+
+impl<E: Any + Send + Sync> SystemParam for EventWriter<'_, E> {
+    type State = Option<Events<E>>;
+    type Item<'w> = EventWriter<'w, E>;
+
+    fn init(_singleton: &mut EcsSingleton, access: &mut Access) -> Self::State {
+        access.add_resource_write::<Events<E>>();
+        None
+    }
+
+    fn prepare(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        take_resource(state, singleton);
+    }
+
+    fn fetch<'w>(state: &'w mut Self::State, _singleton: &'w EcsSingleton) -> Self::Item<'w> {
+        EventWriter {
+            events: state.as_mut().unwrap(),
+        }
+    }
+
+    fn apply(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        return_resource(state, singleton);
+    }
+}
@@ -0,0 +1,47 @@
+use crate::ecs::ecs::{component::IComponent, config::EntityType, singleton::EcsSingleton};
+
+/// Points at the entity this entity is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parent(pub EntityType);
+
+impl IComponent for Parent {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+/// Entities attached to this entity, in spawn order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Children(pub Vec<EntityType>);
+
+impl IComponent for Children {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+/// Runs before `entities` are destroyed: drops them from their parents'
+/// `Children`, removing the component once it is empty, and detaches
+/// their surviving children. Links between two destroyed entities are left
+/// to the caller.
+pub(crate) fn hierarchy_entities_destroyed(singleton: &mut EcsSingleton, entities: &[EntityType]) {
+    for entity in entities {
+        if singleton.has_component::<Parent>(*entity) {
+            let parent = singleton.get_component::<Parent>(*entity).0;
+            if !entities.contains(&parent) && singleton.has_component::<Children>(parent) {
+                let empty = singleton.get_component_mut::<Children, _, _>(parent, |children| {
+                    children.0.retain(|child| child != entity);
+                    children.0.is_empty()
+                });
+                if empty {
+                    singleton.remove_component::<Children>(parent);
+                }
+            }
+        }
+
+        if singleton.has_component::<Children>(*entity) {
+            let children = singleton.get_component::<Children>(*entity).0.clone();
+            for child in children {
+                if !entities.contains(&child) && singleton.has_component::<Parent>(child) {
+                    singleton.remove_component::<Parent>(child);
+                }
+            }
+        }
+    }
+}
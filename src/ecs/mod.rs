@@ -1,13 +1,64 @@
+// Instrumentation that only exists with the `tracing` feature; without it
+// these expand to nothing, so call sites cost nothing.
+//
+// `ecs_span!(guard, ...)` enters a span that lasts until `guard` goes out of
+// scope.
+#[cfg(feature = "tracing")]
+macro_rules! ecs_span {
+    ($guard:ident, $($arg:tt)*) => {
+        let $guard = tracing::debug_span!($($arg)*).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! ecs_span {
+    ($guard:ident, $($arg:tt)*) => {};
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! ecs_event {
+    ($($arg:tt)*) => {
+        tracing::trace!($($arg)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! ecs_event {
+    ($($arg:tt)*) => {};
+}
+
+mod builtin;
 mod component;
 mod config;
+mod diagnostics;
 mod entity;
+mod event;
+mod hierarchy;
+#[cfg(any(test, feature = "model"))]
+mod model;
+mod reflect;
+mod relation;
+mod resource;
+mod scene;
 mod singleton;
+mod state;
 mod system;
 
 pub mod ecs {
+    pub use super::builtin::*;
     pub use super::component::*;
     pub use super::config::*;
+    pub use super::diagnostics::*;
     pub use super::entity::*;
+    pub use super::event::*;
+    pub use super::hierarchy::*;
+    #[cfg(feature = "model")]
+    pub use super::model::*;
+    pub use super::reflect::*;
+    pub use super::relation::*;
+    pub use super::resource::*;
+    pub use super::scene::*;
     pub use super::singleton::*;
+    pub use super::state::*;
     pub use super::system::*;
 }
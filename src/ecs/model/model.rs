@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+
+use crate::ecs::ecs::{
+    component::IComponent,
+    config::{EntityType, MAX_ENTITIES},
+    query::{Query, QueryState},
+    singleton::EcsSingleton,
+};
+
+/// Component kinds a generated workload can touch: a cloneable component, a
+/// component that can't be cloned and a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    Position,
+    Velocity,
+    Frozen,
+}
+
+/// One step of a generated workload. Entity operands are indices into the
+/// live entities in id order, wrapped around, and spawns that would exceed
+/// `MAX_ENTITIES` are skipped, so every op is valid whatever ran before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Spawn,
+    /// Spawns `count % 8 + 1` entities with a position and a velocity.
+    SpawnBatch {
+        count: u8,
+        value: u32,
+    },
+    Despawn(u8),
+    /// Despawns up to `count % 4 + 1` consecutive live entities.
+    DespawnBatch {
+        first: u8,
+        count: u8,
+    },
+    /// Adds the part, replacing the value if the entity already has it.
+    Insert(u8, Part, u32),
+    Remove(u8, Part),
+    Clone(u8),
+    /// Compares a long-lived cached query against the model.
+    Query,
+    /// Runs the systems once.
+    Update,
+}
+
+impl Op {
+    /// Turns arbitrary bytes into ops, three bytes each, for fuzzers.
+    pub fn decode(bytes: &[u8]) -> Vec<Op> {
+        bytes
+            .chunks_exact(3)
+            .map(|chunk| {
+                let (a, b) = (chunk[1], chunk[2]);
+                let part = match b % 3 {
+                    0 => Part::Position,
+                    1 => Part::Velocity,
+                    _ => Part::Frozen,
+                };
+                match chunk[0] % 9 {
+                    0 => Op::Spawn,
+                    1 => Op::SpawnBatch {
+                        count: a,
+                        value: b as u32,
+                    },
+                    2 => Op::Despawn(a),
+                    3 => Op::DespawnBatch { first: a, count: b },
+                    4 => Op::Insert(a, part, b as u32),
+                    5 => Op::Remove(a, part),
+                    6 => Op::Clone(a),
+                    7 => Op::Query,
+                    _ => Op::Update,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Position(u32);
+
+impl IComponent for Position {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+#[derive(Debug)]
+struct Velocity(u32);
+
+impl IComponent for Velocity {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+struct Frozen;
+
+impl IComponent for Frozen {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+fn drift(mut query: Query<(&mut Position, &Velocity)>) {
+    query.for_each(|(position, velocity)| position.0 = position.0.wrapping_add(velocity.0));
+}
+
+/// What the world should contain for one entity.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct ModelEntity {
+    position: Option<u32>,
+    velocity: Option<u32>,
+    frozen: bool,
+}
+
+/// An `EcsSingleton` and a plain map of what it should contain, driven by
+/// the same ops.
+pub struct Harness {
+    singleton: EcsSingleton,
+    query: QueryState<(&'static Position, &'static Velocity)>,
+    model: BTreeMap<EntityType, ModelEntity>,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let mut singleton = EcsSingleton::new();
+        singleton.register_cloneable_component::<Position>();
+        singleton.register_component::<Velocity>();
+        singleton.register_tag::<Frozen>();
+        singleton.register_system_func(drift);
+        let query = singleton.query();
+        Self {
+            singleton,
+            query,
+            model: BTreeMap::new(),
+        }
+    }
+
+    fn has_room(&self, count: usize) -> bool {
+        self.model.len() + count <= MAX_ENTITIES as usize
+    }
+
+    fn pick(&self, index: u8) -> Option<EntityType> {
+        if self.model.is_empty() {
+            return None;
+        }
+        self.model
+            .keys()
+            .nth(index as usize % self.model.len())
+            .copied()
+    }
+
+    /// Applies `op` to both sides, then panics if they disagree or the world
+    /// breaks one of its own invariants.
+    pub fn apply(&mut self, op: Op) {
+        match op {
+            Op::Spawn if self.has_room(1) => {
+                let entity = self.singleton.create_entity();
+                self.model.insert(entity, ModelEntity::default());
+            }
+            Op::SpawnBatch { count, value } if self.has_room(count as usize % 8 + 1) => {
+                let bundles = (0..count % 8 + 1).map(|_| (Position(value), Velocity(1)));
+                for entity in self.singleton.spawn_batch(bundles) {
+                    let spawned = ModelEntity {
+                        position: Some(value),
+                        velocity: Some(1),
+                        frozen: false,
+                    };
+                    self.model.insert(entity, spawned);
+                }
+            }
+            Op::Despawn(index) => {
+                if let Some(entity) = self.pick(index) {
+                    self.singleton.destroy_entity(entity);
+                    self.model.remove(&entity);
+                }
+            }
+            Op::DespawnBatch { first, count } => {
+                let Some(first) = self.pick(first) else {
+                    return self.check(op);
+                };
+                let entities: Vec<EntityType> = self
+                    .model
+                    .range(first..)
+                    .take(count as usize % 4 + 1)
+                    .map(|(entity, _)| *entity)
+                    .collect();
+                self.singleton.despawn_batch(&entities);
+                for entity in entities {
+                    self.model.remove(&entity);
+                }
+            }
+            Op::Insert(index, part, value) => {
+                if let Some(entity) = self.pick(index) {
+                    self.insert(entity, part, value);
+                }
+            }
+            Op::Remove(index, part) => {
+                if let Some(entity) = self.pick(index) {
+                    self.remove(entity, part);
+                }
+            }
+            Op::Clone(index) if self.has_room(1) => {
+                if let Some(src) = self.pick(index) {
+                    let dst = self.singleton.clone_entity(src);
+                    let cloned = ModelEntity {
+                        velocity: None,
+                        ..self.model[&src].clone()
+                    };
+                    self.model.insert(dst, cloned);
+                }
+            }
+            Op::Query => {
+                let expected: Vec<(EntityType, u32, u32)> = self
+                    .model
+                    .iter()
+                    .filter_map(|(entity, model)| Some((*entity, model.position?, model.velocity?)))
+                    .collect();
+                let mut found = Vec::new();
+                self.query.query(&self.singleton).for_each_with_entity(
+                    |entity, (position, velocity)| found.push((entity, position.0, velocity.0)),
+                );
+                assert_eq!(found, expected, "cached query diverged from the model");
+            }
+            Op::Update => {
+                self.singleton.update();
+                for model in self.model.values_mut() {
+                    if let (Some(position), Some(velocity)) = (&mut model.position, model.velocity)
+                    {
+                        *position = position.wrapping_add(velocity);
+                    }
+                }
+            }
+            Op::Spawn | Op::SpawnBatch { .. } | Op::Clone(_) => {}
+        }
+        self.check(op);
+    }
+
+    fn insert(&mut self, entity: EntityType, part: Part, value: u32) {
+        let model = self.model.get_mut(&entity).unwrap();
+        match part {
+            Part::Position => {
+                if model.position.replace(value).is_some() {
+                    self.singleton.remove_component::<Position>(entity);
+                }
+                self.singleton.add_component(entity, Position(value));
+            }
+            Part::Velocity => {
+                if model.velocity.replace(value).is_some() {
+                    self.singleton.remove_component::<Velocity>(entity);
+                }
+                self.singleton.add_component(entity, Velocity(value));
+            }
+            Part::Frozen => {
+                model.frozen = true;
+                self.singleton.add_tag::<Frozen>(entity);
+            }
+        }
+    }
+
+    fn remove(&mut self, entity: EntityType, part: Part) {
+        let model = self.model.get_mut(&entity).unwrap();
+        match part {
+            Part::Position => {
+                if model.position.take().is_some() {
+                    self.singleton.remove_component::<Position>(entity);
+                }
+            }
+            Part::Velocity => {
+                if model.velocity.take().is_some() {
+                    self.singleton.remove_component::<Velocity>(entity);
+                }
+            }
+            Part::Frozen => {
+                model.frozen = false;
+                self.singleton.remove_tag::<Frozen>(entity);
+            }
+        }
+    }
+
+    fn check(&self, op: Op) {
+        let singleton = &self.singleton;
+        let violations = singleton.validate();
+        assert!(violations.is_empty(), "after {:?}: {:?}", op, violations);
+
+        let alive: Vec<EntityType> = singleton.entities().map(|(entity, _)| entity).collect();
+        let expected: Vec<EntityType> = self.model.keys().copied().collect();
+        assert_eq!(alive, expected, "after {:?}: live entities diverged", op);
+        assert_eq!(singleton.entity_count(), self.model.len());
+
+        for (entity, model) in self.model.iter() {
+            let actual = ModelEntity {
+                position: singleton
+                    .has_component::<Position>(*entity)
+                    .then(|| singleton.get_component::<Position>(*entity).0),
+                velocity: singleton
+                    .has_component::<Velocity>(*entity)
+                    .then(|| singleton.get_component::<Velocity>(*entity).0),
+                frozen: singleton.has_component::<Frozen>(*entity),
+            };
+            assert_eq!(&actual, model, "after {:?}: entity {} diverged", op, entity);
+        }
+    }
+}
+
+/// Runs `ops` against a fresh world and the reference model, panicking at
+/// the first divergence.
+pub fn run(ops: &[Op]) {
+    let mut harness = Harness::new();
+    for op in ops {
+        harness.apply(*op);
+    }
+}
+
+#[cfg(test)]
+mod model {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn part() -> impl Strategy<Value = Part> {
+        prop_oneof![
+            Just(Part::Position),
+            Just(Part::Velocity),
+            Just(Part::Frozen),
+        ]
+    }
+
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            Just(Op::Spawn),
+            (any::<u8>(), any::<u32>()).prop_map(|(count, value)| Op::SpawnBatch { count, value }),
+            any::<u8>().prop_map(Op::Despawn),
+            (any::<u8>(), any::<u8>()).prop_map(|(first, count)| Op::DespawnBatch { first, count }),
+            (any::<u8>(), part(), any::<u32>())
+                .prop_map(|(index, part, value)| Op::Insert(index, part, value)),
+            (any::<u8>(), part()).prop_map(|(index, part)| Op::Remove(index, part)),
+            any::<u8>().prop_map(Op::Clone),
+            Just(Op::Query),
+            Just(Op::Update),
+        ]
+    }
+
+    proptest! {
+        // Every op runs a full `validate`, which is slow in debug builds.
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_world_matches_model(ops in proptest::collection::vec(op(), 0..100)) {
+            run(&ops);
+        }
+    }
+
+    #[test]
+    fn test_spawns_stop_at_max_entities() {
+        let mut harness = Harness::new();
+        let bundles = (2..MAX_ENTITIES).map(|_| (Position(0), Velocity(1)));
+        for entity in harness.singleton.spawn_batch(bundles) {
+            let spawned = ModelEntity {
+                position: Some(0),
+                velocity: Some(1),
+                frozen: false,
+            };
+            harness.model.insert(entity, spawned);
+        }
+
+        harness.apply(Op::SpawnBatch { count: 7, value: 1 });
+        harness.apply(Op::Spawn);
+        harness.apply(Op::Clone(0));
+        harness.apply(Op::Spawn);
+        assert_eq!(harness.model.len(), MAX_ENTITIES as usize);
+    }
+
+    #[test]
+    fn test_decode_covers_every_op() {
+        let bytes: Vec<u8> = (0..9).flat_map(|kind| [kind, 3, 2]).collect();
+        let ops = Op::decode(&bytes);
+        assert_eq!(ops.len(), 9);
+        assert_eq!(ops[4], Op::Insert(3, Part::Frozen, 2));
+        run(&ops);
+    }
+}
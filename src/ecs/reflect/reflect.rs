@@ -0,0 +1,277 @@
+use std::{any::Any, fmt};
+
+use glam::{Vec2, Vec3, Vec4};
+
+/// A leaf value read from or written to a reflected field.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl ReflectValue {
+    /// Parses a value typed by a user, e.g. in the inspector.
+    pub fn parse(input: &str) -> Self {
+        if let Ok(value) = input.parse::<bool>() {
+            ReflectValue::Bool(value)
+        } else if let Ok(value) = input.parse::<i64>() {
+            ReflectValue::Int(value)
+        } else if let Ok(value) = input.parse::<f64>() {
+            ReflectValue::Float(value)
+        } else {
+            ReflectValue::String(input.trim_matches('"').to_string())
+        }
+    }
+}
+
+impl fmt::Display for ReflectValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectValue::Bool(value) => write!(f, "{}", value),
+            ReflectValue::Int(value) => write!(f, "{}", value),
+            ReflectValue::Float(value) => write!(f, "{}", value),
+            ReflectValue::String(value) => write!(f, "{:?}", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReflectError {
+    UnknownComponent(String),
+    NotReflected(String),
+    MissingComponent(String),
+    UnknownField(String),
+    NotAValue(String),
+    TypeMismatch {
+        expected: &'static str,
+        found: ReflectValue,
+    },
+}
+
+/// Runtime access to the fields of a component without knowing its concrete
+/// type. Structs expose named fields, leaves expose a `ReflectValue`.
+pub trait Reflect: Any {
+    /// The type's name without its module path or generic arguments.
+    fn reflect_type_name(&self) -> &'static str {
+        let name = std::any::type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name)
+    }
+
+    fn field_names(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+        None
+    }
+
+    fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+        None
+    }
+
+    fn value(&self) -> Option<ReflectValue> {
+        None
+    }
+
+    fn set_value(&mut self, _value: ReflectValue) -> Result<(), ReflectError> {
+        Err(ReflectError::NotAValue(
+            self.reflect_type_name().to_string(),
+        ))
+    }
+
+    fn reflect_debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(value) = self.value() {
+            return write!(f, "{}", value);
+        }
+        let mut debug = f.debug_struct(self.reflect_type_name());
+        for name in self.field_names() {
+            if let Some(field) = self.field(name) {
+                debug.field(name, &field);
+            }
+        }
+        debug.finish()
+    }
+}
+
+impl dyn Reflect {
+    /// Follows a dotted path such as `position.x`; an empty path is `self`.
+    pub fn path(&self, path: &str) -> Result<&dyn Reflect, ReflectError> {
+        let mut current = self;
+        for name in path.split('.').filter(|s| !s.is_empty()) {
+            current = current
+                .field(name)
+                .ok_or_else(|| ReflectError::UnknownField(path.to_string()))?;
+        }
+        Ok(current)
+    }
+
+    pub fn path_mut(&mut self, path: &str) -> Result<&mut dyn Reflect, ReflectError> {
+        let mut current = self;
+        for name in path.split('.').filter(|s| !s.is_empty()) {
+            current = current
+                .field_mut(name)
+                .ok_or_else(|| ReflectError::UnknownField(path.to_string()))?;
+        }
+        Ok(current)
+    }
+
+    pub fn get_path(&self, path: &str) -> Result<ReflectValue, ReflectError> {
+        self.path(path)?
+            .value()
+            .ok_or_else(|| ReflectError::NotAValue(path.to_string()))
+    }
+
+    pub fn set_path(&mut self, path: &str, value: ReflectValue) -> Result<(), ReflectError> {
+        self.path_mut(path)?.set_value(value)
+    }
+
+    /// Leaves become JSON scalars and structs become objects keyed by field
+    /// name.
+    pub fn to_json(&self) -> serde_json::Value {
+        match self.value() {
+            Some(ReflectValue::Bool(value)) => value.into(),
+            Some(ReflectValue::Int(value)) => value.into(),
+            Some(ReflectValue::Float(value)) => value.into(),
+            Some(ReflectValue::String(value)) => value.into(),
+            None => self
+                .field_names()
+                .into_iter()
+                .filter_map(|name| Some((name.to_string(), self.field(name)?.to_json())))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+        }
+    }
+}
+
+impl fmt::Debug for dyn Reflect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reflect_debug(f)
+    }
+}
+
+/// Implements `Reflect` for a struct with named fields that are themselves
+/// `Reflect`.
+#[macro_export]
+macro_rules! impl_reflect_struct {
+    ($ty:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::reflect::Reflect for $ty {
+            fn field_names(&self) -> Vec<&'static str> {
+                vec![$(stringify!($field)),*]
+            }
+
+            fn field(&self, name: &str) -> Option<&dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&self.$field),)*
+                    _ => None,
+                }
+            }
+
+            fn field_mut(
+                &mut self,
+                name: &str,
+            ) -> Option<&mut dyn $crate::reflect::Reflect> {
+                match name {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_reflect_int {
+    ($($ty:ty),*) => {$(
+        impl Reflect for $ty {
+            fn value(&self) -> Option<ReflectValue> {
+                Some(ReflectValue::Int(*self as i64))
+            }
+
+            fn set_value(&mut self, value: ReflectValue) -> Result<(), ReflectError> {
+                match value {
+                    ReflectValue::Int(v) => <$ty>::try_from(v)
+                        .map(|v| *self = v)
+                        .map_err(|_| ReflectError::TypeMismatch {
+                            expected: stringify!($ty),
+                            found: value,
+                        }),
+                    found => Err(ReflectError::TypeMismatch {
+                        expected: stringify!($ty),
+                        found,
+                    }),
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_reflect_float {
+    ($($ty:ty),*) => {$(
+        impl Reflect for $ty {
+            fn value(&self) -> Option<ReflectValue> {
+                Some(ReflectValue::Float(*self as f64))
+            }
+
+            fn set_value(&mut self, value: ReflectValue) -> Result<(), ReflectError> {
+                match value {
+                    ReflectValue::Float(v) => *self = v as $ty,
+                    ReflectValue::Int(v) => *self = v as $ty,
+                    found => {
+                        return Err(ReflectError::TypeMismatch {
+                            expected: stringify!($ty),
+                            found,
+                        })
+                    }
+                }
+                Ok(())
+            }
+        }
+    )*};
+}
+
+impl_reflect_int!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+impl_reflect_float!(f32, f64);
+
+impl Reflect for bool {
+    fn value(&self) -> Option<ReflectValue> {
+        Some(ReflectValue::Bool(*self))
+    }
+
+    fn set_value(&mut self, value: ReflectValue) -> Result<(), ReflectError> {
+        match value {
+            ReflectValue::Bool(v) => {
+                *self = v;
+                Ok(())
+            }
+            found => Err(ReflectError::TypeMismatch {
+                expected: "bool",
+                found,
+            }),
+        }
+    }
+}
+
+impl Reflect for String {
+    fn value(&self) -> Option<ReflectValue> {
+        Some(ReflectValue::String(self.clone()))
+    }
+
+    fn set_value(&mut self, value: ReflectValue) -> Result<(), ReflectError> {
+        match value {
+            ReflectValue::String(v) => {
+                *self = v;
+                Ok(())
+            }
+            found => Err(ReflectError::TypeMismatch {
+                expected: "String",
+                found,
+            }),
+        }
+    }
+}
+
+impl_reflect_struct!(Vec2 { x, y });
+impl_reflect_struct!(Vec3 { x, y, z });
+impl_reflect_struct!(Vec4 { x, y, z, w });
@@ -0,0 +1,282 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    marker::PhantomData,
+};
+
+use crate::ecs::ecs::{
+    component::IComponent, config::EntityType, resource_manager::Resource, singleton::EcsSingleton,
+};
+
+/// A kind of relationship between entities, e.g. `struct Targets;`.
+pub trait RelationKind: Any + Send + Sync {}
+
+impl<T: Any + Send + Sync> RelationKind for T {}
+
+/// How many targets a source may have for one relation kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelationArity {
+    /// Adding a relation replaces the source's previous target.
+    ManyToOne,
+    ManyToMany,
+}
+
+/// What happens to the sources of a relation when its target is destroyed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RelationCleanup {
+    /// The target is dropped from each source's `Relation<R>`, and the
+    /// component is removed once no targets are left.
+    #[default]
+    RemoveRelation,
+    /// Every source is destroyed as well, cascading through their relations.
+    DespawnSource,
+}
+
+/// Points from this entity at the entities it is related to through `R`.
+/// Added with `EcsSingleton::add_relation` so the reverse index stays in
+/// sync; removing the component unlinks all of its targets.
+#[derive(Debug)]
+pub struct Relation<R: RelationKind> {
+    targets: Vec<EntityType>,
+    marker: PhantomData<fn() -> R>,
+}
+
+impl<R: RelationKind> Relation<R> {
+    /// The first target, which is the only one for `ManyToOne` relations.
+    pub fn target(&self) -> EntityType {
+        self.targets[0]
+    }
+
+    /// Targets in the order they were added.
+    pub fn targets(&self) -> &[EntityType] {
+        &self.targets
+    }
+}
+
+impl<R: RelationKind> Clone for Relation<R> {
+    fn clone(&self) -> Self {
+        Self {
+            targets: self.targets.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R: RelationKind> IComponent for Relation<R> {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+/// Both directions of every `R` relation, kept as a resource by
+/// `EcsSingleton::register_relation`.
+#[derive(Debug)]
+pub struct RelationIndex<R: RelationKind> {
+    arity: RelationArity,
+    cleanup: RelationCleanup,
+    targets: HashMap<EntityType, Vec<EntityType>>,
+    sources: HashMap<EntityType, Vec<EntityType>>,
+    marker: PhantomData<fn() -> R>,
+}
+
+impl<R: RelationKind> Resource for RelationIndex<R> {}
+
+impl<R: RelationKind> RelationIndex<R> {
+    pub fn arity(&self) -> RelationArity {
+        self.arity
+    }
+
+    pub fn cleanup(&self) -> RelationCleanup {
+        self.cleanup
+    }
+
+    /// Entities that point at `target`, in the order they were related.
+    pub fn sources(&self, target: EntityType) -> &[EntityType] {
+        self.sources.get(&target).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn targets(&self, source: EntityType) -> &[EntityType] {
+        self.targets.get(&source).map_or(&[], Vec::as_slice)
+    }
+
+    fn insert(&mut self, source: EntityType, target: EntityType) {
+        self.targets.entry(source).or_default().push(target);
+        self.sources.entry(target).or_default().push(source);
+    }
+
+    fn remove(&mut self, source: EntityType, target: EntityType) {
+        remove_from(&mut self.targets, source, target);
+        remove_from(&mut self.sources, target, source);
+    }
+}
+
+fn remove_from(map: &mut HashMap<EntityType, Vec<EntityType>>, key: EntityType, value: EntityType) {
+    if let Some(values) = map.get_mut(&key) {
+        values.retain(|v| *v != value);
+        if values.is_empty() {
+            map.remove(&key);
+        }
+    }
+}
+
+pub(crate) fn init_relation<R: RelationKind>(
+    singleton: &mut EcsSingleton,
+    arity: RelationArity,
+    cleanup: RelationCleanup,
+) {
+    singleton.register_component::<Relation<R>>();
+    singleton.insert_resource(RelationIndex::<R> {
+        arity,
+        cleanup,
+        targets: HashMap::new(),
+        sources: HashMap::new(),
+        marker: PhantomData,
+    });
+}
+
+fn index_mut<R: RelationKind>(singleton: &mut EcsSingleton) -> &mut RelationIndex<R> {
+    singleton
+        .get_resource_mut::<RelationIndex<R>>()
+        .expect("Relation not registered")
+}
+
+pub(crate) fn add_relation<R: RelationKind>(
+    singleton: &mut EcsSingleton,
+    source: EntityType,
+    target: EntityType,
+) {
+    assert!(
+        singleton.is_alive(source),
+        "Relating an entity that is not alive."
+    );
+    assert!(
+        singleton.is_alive(target),
+        "Relating to an entity that is not alive."
+    );
+    let index = index_mut::<R>(singleton);
+    if index.targets(source).contains(&target) {
+        return;
+    }
+    if index.arity == RelationArity::ManyToOne {
+        if let Some(&previous) = index.targets(source).first() {
+            remove_relation::<R>(singleton, source, previous);
+        }
+    }
+    index_mut::<R>(singleton).insert(source, target);
+
+    if singleton.has_component::<Relation<R>>(source) {
+        singleton.get_component_mut::<Relation<R>, _, _>(source, |relation| {
+            relation.targets.push(target)
+        });
+    } else {
+        let targets = vec![target];
+        let marker = PhantomData;
+        singleton.add_component(source, Relation::<R> { targets, marker });
+    }
+}
+
+pub(crate) fn remove_relation<R: RelationKind>(
+    singleton: &mut EcsSingleton,
+    source: EntityType,
+    target: EntityType,
+) {
+    let index = index_mut::<R>(singleton);
+    if !index.targets(source).contains(&target) {
+        return;
+    }
+    index.remove(source, target);
+    let empty = index.targets(source).is_empty();
+
+    if empty {
+        singleton.remove_component::<Relation<R>>(source);
+    } else {
+        singleton.get_component_mut::<Relation<R>, _, _>(source, |relation| {
+            relation.targets.retain(|t| *t != target)
+        });
+    }
+}
+
+/// What the world calls to keep the index of one relation kind in sync.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RelationHooks {
+    /// `TypeId` of `Relation<R>`.
+    pub type_id: TypeId,
+    pub entities_destroyed: fn(&mut EcsSingleton, &[EntityType]),
+    pub entities_moved: fn(&mut EcsSingleton, &[EntityType]),
+    pub component_removed: fn(&mut EcsSingleton, EntityType),
+}
+
+impl RelationHooks {
+    pub fn of<R: RelationKind>() -> Self {
+        Self {
+            type_id: TypeId::of::<Relation<R>>(),
+            entities_destroyed: relation_entities_destroyed::<R>,
+            entities_moved: relation_entities_moved::<R>,
+            component_removed: relation_component_removed::<R>,
+        }
+    }
+}
+
+/// Runs before the `Relation<R>` of `source` is removed, unlinking it from
+/// all of its targets. Removing the last target through `remove_relation`
+/// has already done this.
+fn relation_component_removed<R: RelationKind>(singleton: &mut EcsSingleton, source: EntityType) {
+    let index = index_mut::<R>(singleton);
+    for target in index.targets.remove(&source).unwrap_or_default() {
+        remove_from(&mut index.sources, target, source);
+    }
+}
+
+/// Runs before `entities` are destroyed: unlinks them as sources, then
+/// applies the cleanup policy to whatever pointed at them. Sources that are
+/// in `entities` themselves are left to the caller.
+fn relation_entities_destroyed<R: RelationKind>(
+    singleton: &mut EcsSingleton,
+    entities: &[EntityType],
+) {
+    let cleanup = index_mut::<R>(singleton).cleanup;
+    unlink_entities::<R>(singleton, entities, cleanup);
+}
+
+/// Runs before `entities` are moved to another world. They leave rather
+/// than die, so whatever pointed at them only loses the relation.
+fn relation_entities_moved<R: RelationKind>(singleton: &mut EcsSingleton, entities: &[EntityType]) {
+    unlink_entities::<R>(singleton, entities, RelationCleanup::RemoveRelation);
+}
+
+fn unlink_entities<R: RelationKind>(
+    singleton: &mut EcsSingleton,
+    entities: &[EntityType],
+    cleanup: RelationCleanup,
+) {
+    let index = index_mut::<R>(singleton);
+    for entity in entities {
+        for target in index.targets.remove(entity).unwrap_or_default() {
+            remove_from(&mut index.sources, target, *entity);
+        }
+    }
+
+    let mut orphaned = Vec::new();
+    for entity in entities {
+        for source in index.sources.remove(entity).unwrap_or_default() {
+            remove_from(&mut index.targets, source, *entity);
+            orphaned.push((source, *entity));
+        }
+    }
+
+    for (source, target) in orphaned {
+        if entities.contains(&source) || !singleton.is_alive(source) {
+            continue;
+        }
+        match cleanup {
+            RelationCleanup::RemoveRelation => {
+                if index_mut::<R>(singleton).targets(source).is_empty() {
+                    singleton.remove_component::<Relation<R>>(source);
+                } else {
+                    singleton.get_component_mut::<Relation<R>, _, _>(source, |relation| {
+                        relation.targets.retain(|t| *t != target)
+                    });
+                }
+            }
+            RelationCleanup::DespawnSource => singleton.destroy_entity(source),
+        }
+    }
+}
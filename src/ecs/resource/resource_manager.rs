@@ -0,0 +1,50 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+/// A type that can be stored as a resource, usually implemented with
+/// `#[derive(Resource)]`. Every resource API requires it, from
+/// `EcsSingleton::insert_resource` to `Res` and `ResMut`, so only types
+/// that opted in are stored as resources.
+pub trait Resource: Any + Send + Sync {}
+
+/// World-wide singletons that aren't attached to an entity, e.g. the frame
+/// time or the current game state.
+#[derive(Debug, Default)]
+pub struct ResourceManager {
+    resources: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl ResourceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert<R: Any + Send + Sync>(&mut self, resource: R) {
+        self.resources.insert(TypeId::of::<R>(), Box::new(resource));
+    }
+
+    pub fn remove<R: Any + Send + Sync>(&mut self) -> Option<R> {
+        self.resources
+            .remove(&TypeId::of::<R>())
+            .and_then(|r| r.downcast::<R>().ok())
+            .map(|r| *r)
+    }
+
+    pub fn contains<R: Any>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn get<R: Any>(&self) -> Option<&R> {
+        self.resources
+            .get(&TypeId::of::<R>())
+            .and_then(|r| r.downcast_ref::<R>())
+    }
+
+    pub fn get_mut<R: Any>(&mut self) -> Option<&mut R> {
+        self.resources
+            .get_mut(&TypeId::of::<R>())
+            .and_then(|r| r.downcast_mut::<R>())
+    }
+}
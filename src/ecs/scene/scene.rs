@@ -0,0 +1,352 @@
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ecs::ecs::{component::BoxedComponent, component_manager::ComponentManager};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(String),
+    Parse(String),
+    UnknownComponent(String),
+    NotSceneComponent(String),
+    InvalidComponent {
+        component: String,
+        message: String,
+    },
+    UnknownParent(String),
+    DuplicateName(String),
+    /// An entity is its own ancestor; holds the name of one on the cycle.
+    ParentCycle(String),
+    UnknownOverride {
+        entity: String,
+        component: String,
+    },
+}
+
+/// A declarative description of one or more entities, authored as data and
+/// spawned into a world with `EcsSingleton::instantiate`.
+///
+/// Components are keyed by the short type name they were registered under
+/// with `register_scene_component`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scene {
+    #[serde(default)]
+    pub entities: Vec<SceneEntity>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SceneEntity {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Name of another entity in the same scene to attach to.
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+}
+
+/// Per-instance field overrides, keyed by scene entity name and component
+/// name. Values are merged into the authored component data, so only the
+/// changed fields need to be given.
+#[derive(Debug, Clone, Default)]
+pub struct SceneOverrides {
+    entries: HashMap<String, BTreeMap<String, Value>>,
+}
+
+impl SceneOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(mut self, entity: &str, component: &str, value: Value) -> Self {
+        let components = self.entries.entry(entity.to_string()).or_default();
+        match components.get_mut(component) {
+            Some(current) => merge_value(current, value),
+            None => {
+                components.insert(component.to_string(), value);
+            }
+        }
+        self
+    }
+
+    fn get(&self, entity: &str, component: &str) -> Option<&Value> {
+        self.entries.get(entity).and_then(|c| c.get(component))
+    }
+}
+
+/// Components of one scene entity, already built and ready to be inserted.
+pub(crate) struct PreparedEntity {
+    pub components: Vec<(TypeId, BoxedComponent)>,
+    pub parent: Option<usize>,
+}
+
+impl Scene {
+    pub fn from_json(source: &str) -> Result<Self, SceneError> {
+        serde_json::from_str(source).map_err(|e| SceneError::Parse(e.to_string()))
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, SceneError> {
+        ron::from_str(source).map_err(|e| SceneError::Parse(e.to_string()))
+    }
+
+    /// Loads a scene file, picking the format from the `.ron` or `.json`
+    /// extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| SceneError::Io(e.to_string()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ron") => Self::from_ron(&source),
+            _ => Self::from_json(&source),
+        }
+    }
+
+    /// Builds every component of the scene up front so a bad entry fails the
+    /// whole instantiation before any entity is created.
+    pub(crate) fn prepare(
+        &self,
+        component_manager: &ComponentManager,
+        overrides: &SceneOverrides,
+    ) -> Result<Vec<PreparedEntity>, SceneError> {
+        let mut names = HashMap::new();
+        for (index, entity) in self.entities.iter().enumerate() {
+            if let Some(name) = entity.name.as_deref() {
+                if names.insert(name, index).is_some() {
+                    return Err(SceneError::DuplicateName(name.to_string()));
+                }
+            }
+        }
+        for (entity, components) in overrides.entries.iter() {
+            let authored = names
+                .get(entity.as_str())
+                .map(|index| &self.entities[*index].components);
+            for component in components.keys() {
+                if !authored.is_some_and(|authored| authored.contains_key(component)) {
+                    return Err(SceneError::UnknownOverride {
+                        entity: entity.clone(),
+                        component: component.clone(),
+                    });
+                }
+            }
+        }
+
+        let registry = component_manager.registry();
+        let mut prepared = Vec::with_capacity(self.entities.len());
+        for entity in self.entities.iter() {
+            let mut components = Vec::with_capacity(entity.components.len());
+            for (component, value) in entity.components.iter() {
+                let info = registry
+                    .get_by_name(component)
+                    .ok_or_else(|| SceneError::UnknownComponent(component.clone()))?;
+                let (type_id, loader) = info
+                    .type_id
+                    .zip(info.loader)
+                    .ok_or_else(|| SceneError::NotSceneComponent(component.clone()))?;
+
+                let mut value = value.clone();
+                if let Some(patch) = entity
+                    .name
+                    .as_deref()
+                    .and_then(|name| overrides.get(name, component))
+                {
+                    merge_value(&mut value, patch.clone());
+                }
+
+                let data = loader(value).map_err(|e| SceneError::InvalidComponent {
+                    component: component.clone(),
+                    message: e.to_string(),
+                })?;
+                components.push((type_id, data));
+            }
+
+            let parent = match entity.parent.as_deref() {
+                Some(parent) => Some(
+                    *names
+                        .get(parent)
+                        .ok_or_else(|| SceneError::UnknownParent(parent.to_string()))?,
+                ),
+                None => None,
+            };
+            prepared.push(PreparedEntity { components, parent });
+        }
+
+        for (index, entity) in prepared.iter().enumerate() {
+            let mut ancestor = entity.parent;
+            for _ in 0..prepared.len() {
+                match ancestor {
+                    Some(parent) if parent == index => {
+                        let name = self.entities[index].name.clone().unwrap_or_default();
+                        return Err(SceneError::ParentCycle(name));
+                    }
+                    Some(parent) => ancestor = prepared[parent].parent,
+                    None => break,
+                }
+            }
+        }
+        Ok(prepared)
+    }
+}
+
+fn merge_value(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(current) => merge_value(current, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+#[cfg(test)]
+mod scene {
+    use glam::Vec3;
+    use serde_json::json;
+
+    use super::*;
+    use crate::ecs::ecs::{
+        component::IComponent,
+        config::EntityType,
+        hierarchy::{Children, Parent},
+        singleton::EcsSingleton,
+    };
+
+    #[derive(Debug, Deserialize)]
+    struct Transform {
+        position: Vec3,
+    }
+
+    impl IComponent for Transform {
+        fn entity_destroyed(&mut self, _entity: EntityType) {}
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Health {
+        value: i32,
+    }
+
+    impl IComponent for Health {
+        fn entity_destroyed(&mut self, _entity: EntityType) {}
+    }
+
+    const ENEMY: &str = r#"{
+        "entities": [
+            {
+                "name": "body",
+                "components": {
+                    "Transform": { "position": [1.0, 2.0, 3.0] },
+                    "Health": { "value": 10 }
+                }
+            },
+            {
+                "name": "weapon",
+                "parent": "body",
+                "components": { "Transform": { "position": [0.0, 1.0, 0.0] } }
+            }
+        ]
+    }"#;
+
+    fn singleton() -> EcsSingleton {
+        let mut singleton = EcsSingleton::new();
+        singleton.register_scene_component::<Transform>();
+        singleton.register_scene_component::<Health>();
+        singleton
+    }
+
+    #[test]
+    fn test_instantiate_scene_with_hierarchy() {
+        let mut singleton = singleton();
+        let scene = Scene::from_json(ENEMY).unwrap();
+
+        let first = singleton.instantiate(&scene).unwrap();
+        let second = singleton.instantiate(&scene).unwrap();
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|e| !second.contains(e)));
+
+        let (body, weapon) = (first[0], first[1]);
+        assert_eq!(
+            singleton.get_component::<Transform>(body).position,
+            Vec3::new(1.0, 2.0, 3.0)
+        );
+        assert_eq!(singleton.get_component::<Health>(body).value, 10);
+        assert_eq!(*singleton.get_component::<Parent>(weapon), Parent(body));
+        assert_eq!(
+            *singleton.get_component::<Children>(body),
+            Children(vec![weapon])
+        );
+    }
+
+    #[test]
+    fn test_instantiate_with_overrides() {
+        let mut singleton = singleton();
+        let scene = Scene::from_ron(
+            r#"(entities: [(name: Some("body"), components: {
+                "Transform": {"position": [1.0, 2.0, 3.0]},
+                "Health": {"value": 10},
+            })])"#,
+        )
+        .unwrap();
+        let overrides = SceneOverrides::new().set("body", "Health", json!({ "value": 99 }));
+
+        let entities = singleton.instantiate_with(&scene, &overrides).unwrap();
+
+        assert_eq!(singleton.get_component::<Health>(entities[0]).value, 99);
+    }
+
+    #[test]
+    fn test_unknown_component_spawns_nothing() {
+        let mut singleton = singleton();
+        let scene =
+            Scene::from_json(r#"{ "entities": [ { "components": { "Mana": {} } } ] }"#).unwrap();
+
+        let result = singleton.instantiate(&scene);
+
+        assert!(matches!(result, Err(SceneError::UnknownComponent(name)) if name == "Mana"));
+    }
+
+    #[test]
+    fn test_invalid_names_and_parents_spawn_nothing() {
+        let mut singleton = singleton();
+        let instantiate = |singleton: &mut EcsSingleton, source: &str| {
+            singleton.instantiate(&Scene::from_json(source).unwrap())
+        };
+
+        let duplicate = r#"{ "entities": [ { "name": "a" }, { "name": "a" } ] }"#;
+        let result = instantiate(&mut singleton, duplicate);
+        assert!(matches!(result, Err(SceneError::DuplicateName(name)) if name == "a"));
+
+        let own_parent = r#"{ "entities": [ { "name": "a", "parent": "a" } ] }"#;
+        let result = instantiate(&mut singleton, own_parent);
+        assert!(matches!(result, Err(SceneError::ParentCycle(name)) if name == "a"));
+
+        let cycle = r#"{ "entities": [
+            { "name": "a", "parent": "b" },
+            { "name": "b", "parent": "a" }
+        ] }"#;
+        let result = instantiate(&mut singleton, cycle);
+        assert!(matches!(result, Err(SceneError::ParentCycle(_))));
+
+        let scene = Scene::from_json(ENEMY).unwrap();
+        for (entity, component) in [("head", "Health"), ("weapon", "Health")] {
+            let overrides = SceneOverrides::new().set(entity, component, json!({ "value": 1 }));
+            let result = singleton.instantiate_with(&scene, &overrides);
+            assert!(matches!(
+                result,
+                Err(SceneError::UnknownOverride { entity: e, component: c })
+                    if e == entity && c == component
+            ));
+        }
+        assert_eq!(singleton.entity_count(), 0);
+    }
+}
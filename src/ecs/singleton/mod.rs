@@ -1 +1,3 @@
+pub mod shared;
 pub mod singleton;
+pub mod validate;
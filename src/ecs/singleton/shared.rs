@@ -0,0 +1,124 @@
+use std::{
+    any::Any,
+    sync::{Arc, RwLock},
+};
+
+use crate::ecs::ecs::{
+    component::IComponent,
+    config::{EntityType, Signature},
+    query::{Query, QueryState, ReadOnlyQueryData},
+    reflect::{ReflectError, ReflectValue},
+    registry::ComponentInfo,
+    singleton::EcsSingleton,
+};
+
+/// A cloneable, thread-safe handle to one world. Read scopes run at the same
+/// time; a write scope waits for all of them and excludes everything else.
+#[derive(Debug, Clone)]
+pub struct SharedWorld {
+    world: Arc<RwLock<ReadShared>>,
+}
+
+/// The world behind `SharedWorld`'s lock.
+#[derive(Debug)]
+struct ReadShared(EcsSingleton);
+
+// SAFETY: `EcsSingleton` isn't `Sync` because `get_component_mut` writes
+// through `&self`. Shared references to the world only leave the lock as a
+// `WorldReader`, whose methods never write and take only read locks on
+// component arrays; everything else goes through the write lock.
+unsafe impl Sync for ReadShared {}
+
+impl SharedWorld {
+    pub fn new(world: EcsSingleton) -> Self {
+        Self {
+            world: Arc::new(RwLock::new(ReadShared(world))),
+        }
+    }
+
+    /// Runs `f` with read-only access, alongside any other read scopes.
+    pub fn read<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&WorldReader) -> R,
+    {
+        let guard = self.world.read().expect("World lock poisoned");
+        f(&WorldReader { world: &guard.0 })
+    }
+
+    /// Runs `f` with exclusive, mutable access.
+    pub fn write<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut EcsSingleton) -> R,
+    {
+        let mut guard = self.world.write().expect("World lock poisoned");
+        f(&mut guard.0)
+    }
+
+    /// Returns the world once every other handle has been dropped.
+    pub fn into_inner(self) -> Result<EcsSingleton, Self> {
+        match Arc::try_unwrap(self.world) {
+            Ok(world) => Ok(world.into_inner().expect("World lock poisoned").0),
+            Err(world) => Err(Self { world }),
+        }
+    }
+}
+
+/// The read-only part of `EcsSingleton`, handed out by `SharedWorld::read`.
+/// It deliberately has no `get_component_mut`, which would alias the
+/// references `get_component` returns.
+pub struct WorldReader<'a> {
+    world: &'a EcsSingleton,
+}
+
+impl<'a> WorldReader<'a> {
+    pub fn get_component<T: Any + IComponent>(&self, entity: EntityType) -> &'a T {
+        self.world.get_component::<T>(entity)
+    }
+
+    pub fn has_component<T: Any>(&self, entity: EntityType) -> bool {
+        self.world.has_component::<T>(entity)
+    }
+
+    pub fn is_alive(&self, entity: EntityType) -> bool {
+        self.world.is_alive(entity)
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.world.entity_count()
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = (EntityType, &'a Signature)> {
+        self.world.entities()
+    }
+
+    pub fn entity_components(&self, entity: EntityType) -> Vec<ComponentInfo> {
+        self.world.entity_components(entity)
+    }
+
+    pub fn get_component_field(
+        &self,
+        entity: EntityType,
+        component: &str,
+        path: &str,
+    ) -> Result<ReflectValue, ReflectError> {
+        self.world.get_component_field(entity, component, path)
+    }
+
+    pub fn system_entities<T: Any>(&self) -> Vec<EntityType> {
+        self.world.system_entities::<T>()
+    }
+
+    /// Runs a read-only query. Create `state` with `EcsSingleton::query` in a
+    /// write scope; it can be kept and reused across read scopes.
+    pub fn query<'s, Q: ReadOnlyQueryData>(&self, state: &'s mut QueryState<Q>) -> Query<'s, Q>
+    where
+        'a: 's,
+    {
+        state.query(self.world)
+    }
+}
+
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SharedWorld>();
+};
@@ -1,15 +1,38 @@
 use std::{
+    alloc::Layout,
     any::{Any, TypeId},
+    cell::Cell,
+    collections::HashSet,
+    marker::PhantomData,
     sync::{Arc, Mutex},
+    time::Instant,
 };
 
+use serde::de::DeserializeOwned;
+
 use crate::ecs::ecs::{
-    component::IComponent,
+    access::SystemConflict,
+    bundle::{Bundle, BundleStorage},
+    clone::{CloneError, ClonePolicy, ComponentSet, DetachedComponent},
+    component::{BoxedComponent, Component, IComponent},
     component_manager::ComponentManager,
-    config::{EntityType, Signature},
-    entity_manager::EntityManager,
-    system::System,
+    config::{ComponentType, EntityType, Signature},
+    diagnostics::Diagnostics,
+    dynamic::DynamicDropFn,
+    entity_manager::{EntityManager, EntityManagerResponse},
+    event::{self, Events},
+    hierarchy::{self, Children, Parent},
+    param::{self, SystemParamFunction},
+    query::{QueryData, QueryState},
+    reflect::{Reflect, ReflectError, ReflectValue},
+    registry::{ComponentInfo, FreezeError, SharedRegistry},
+    relation::{self, RelationArity, RelationCleanup, RelationHooks, RelationIndex, RelationKind},
+    resource_manager::{Resource, ResourceManager},
+    scene::{Scene, SceneError, SceneOverrides},
+    state::{self, NextState, StateSchedule, StateSchedules, StateScoped, States},
+    system::{System, SystemInfo, TakenRun},
     system_manager::SystemManager,
+    validate::{self, InvariantViolation},
 };
 
 #[derive(Debug)]
@@ -17,29 +40,436 @@ pub struct EcsSingleton {
     component_manager: Box<ComponentManager>,
     entity_manager: Box<EntityManager>,
     system_manager: Box<SystemManager>,
+    resource_manager: Box<ResourceManager>,
+    state_transitions: Vec<fn(&mut EcsSingleton)>,
+    event_updates: Vec<fn(&mut EcsSingleton)>,
+    relations: Vec<RelationHooks>,
+    validate_each_tick: bool,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+// A world must be movable to worker threads. It is not `Sync`:
+// `get_component` hands out references that outlive the array lock and
+// `get_component_mut` writes through `&self`.
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<EcsSingleton>();
+};
+
+impl Default for EcsSingleton {
+    fn default() -> Self {
+        Self::new()
+    }
 }
+
 impl EcsSingleton {
     pub fn new() -> Self {
-        Self {
-            component_manager: Box::new(ComponentManager::new()),
+        Self::with_registry(SharedRegistry::default())
+    }
+
+    /// Creates a world that shares `registry` with other worlds, so the same
+    /// component type gets the same `ComponentType` id in each of them.
+    pub fn with_registry(registry: SharedRegistry) -> Self {
+        let mut singleton = Self {
+            component_manager: Box::new(ComponentManager::with_registry(registry)),
             entity_manager: Box::new(EntityManager::create()),
             system_manager: Box::new(SystemManager::new()),
+            resource_manager: Box::new(ResourceManager::new()),
+            state_transitions: Vec::new(),
+            event_updates: Vec::new(),
+            relations: Vec::new(),
+            validate_each_tick: false,
+            _not_sync: PhantomData,
+        };
+        if !singleton
+            .component_manager
+            .registry()
+            .contains(TypeId::of::<Parent>())
+        {
+            singleton.register_component::<Parent>();
+            singleton.register_component::<Children>();
+        }
+        singleton
+    }
+
+    pub(crate) fn component_manager(&self) -> &ComponentManager {
+        &self.component_manager
+    }
+
+    pub(crate) fn entity_manager(&self) -> &EntityManager {
+        &self.entity_manager
+    }
+
+    pub(crate) fn system_manager(&self) -> &SystemManager {
+        &self.system_manager
+    }
+
+    pub(crate) fn component_manager_mut(&mut self) -> &mut ComponentManager {
+        &mut self.component_manager
+    }
+
+    pub fn shared_registry(&self) -> SharedRegistry {
+        self.component_manager.shared_registry()
+    }
+
+    /// Moves `entity` and all of its components into `dst`, returning its id
+    /// there. Both worlds must share a registry. `Parent`, `Children` and
+    /// relations are dropped since they refer to entities left behind in this
+    /// world; entities related to it lose the relation whatever its cleanup
+    /// policy, as the entity isn't destroyed.
+    pub fn move_entity(&mut self, entity: EntityType, dst: &mut EcsSingleton) -> EntityType {
+        assert!(
+            Arc::ptr_eq(&self.shared_registry(), &dst.shared_registry()),
+            "Moving an entity between worlds that don't share a registry."
+        );
+        assert!(self.is_alive(entity), "Moving an entity that is not alive.");
+        let new_entity = dst.create_entity();
+        self.unlink_moved(entity);
+
+        let mut hierarchy = vec![TypeId::of::<Parent>(), TypeId::of::<Children>()];
+        hierarchy.extend(self.relations.iter().map(|hooks| hooks.type_id));
+
+        let mut moved = Vec::new();
+        for info in self.entity_components(entity) {
+            if info
+                .type_id
+                .is_some_and(|type_id| hierarchy.contains(&type_id))
+            {
+                continue;
+            }
+            if let Some(component) = self.component_manager.take_component(&info, entity) {
+                moved.push(component);
+            }
+        }
+        ecs_event!(entity, "entity despawned");
+        self.release_entity(entity);
+        self.component_manager.entity_destroyed(entity);
+        self.refresh_systems();
+
+        dst.attach_components(new_entity, moved);
+        new_entity
+    }
+
+    fn unlink_moved(&mut self, entity: EntityType) {
+        hierarchy::hierarchy_entities_destroyed(self, &[entity]);
+        for hooks in self.relations.clone() {
+            (hooks.entities_moved)(self, &[entity]);
+        }
+    }
+
+    fn attach_components(&mut self, entity: EntityType, components: Vec<DetachedComponent>) {
+        for component in components {
+            match component {
+                DetachedComponent::Typed(type_id, data) => {
+                    self.add_component_boxed(type_id, entity, data)
+                }
+                DetachedComponent::Dynamic(component_type, bytes) => {
+                    self.add_dynamic_component(entity, component_type, &bytes)
+                }
+                DetachedComponent::Tag(component_type) => {
+                    self.signature_insert(entity, component_type)
+                }
+            }
         }
     }
     pub fn create_entity(&mut self) -> u32 {
-        self.entity_manager.create_entity()
+        let entity = self.entity_manager.create_entity();
+        ecs_event!(entity, "entity spawned");
+        self.refresh_systems();
+        entity
     }
 
     pub fn destroy_entity(&mut self, entity: EntityType) {
-        let _ = self.entity_manager.destroy_entity(entity);
+        assert!(
+            self.is_alive(entity),
+            "Destroying an entity that is not alive."
+        );
+        ecs_event!(entity, "entity despawned");
+        self.cleanup_relations(&[entity]);
+        self.release_entity(entity);
         self.component_manager.entity_destroyed(entity);
-        self.system_manager.entity_destroyed(entity);
+        self.refresh_systems();
+    }
+
+    fn cleanup_relations(&mut self, entities: &[EntityType]) {
+        hierarchy::hierarchy_entities_destroyed(self, entities);
+        for hooks in self.relations.clone() {
+            (hooks.entities_destroyed)(self, entities);
+        }
+    }
+
+    fn release_entity(&mut self, entity: EntityType) {
+        let response = self.entity_manager.destroy_entity(entity);
+        assert!(
+            response == EntityManagerResponse::RemovedEntity,
+            "Destroying an entity that is not alive."
+        );
+    }
+
+    pub fn is_alive(&self, entity: EntityType) -> bool {
+        self.entity_manager.is_alive(entity)
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.entity_manager.living() as usize
+    }
+
+    /// Every live entity in id order, with its signature.
+    pub fn entities(&self) -> impl Iterator<Item = (EntityType, &Signature)> {
+        self.entity_manager.iter()
+    }
+
+    /// Spawns one entity per bundle. Ids are reserved in bulk, each component
+    /// column is written in one pass and systems are matched once for the
+    /// shared signature.
+    pub fn spawn_batch<B: Bundle, I: IntoIterator<Item = B>>(
+        &mut self,
+        bundles: I,
+    ) -> Vec<EntityType> {
+        let bundles: Vec<B> = bundles.into_iter().collect();
+        let mut storage = BundleStorage::new(&mut self.component_manager);
+        let signature = B::signature(&mut storage);
+        let entities = self.entity_manager.create_entities(bundles.len());
+        B::insert_batch(bundles, &entities, &mut storage);
+        for entity in entities.iter() {
+            self.entity_manager
+                .set_signature(*entity, signature.clone());
+            ecs_event!(entity = *entity, "entity spawned");
+            #[cfg(feature = "tracing")]
+            for component_type in signature.iter() {
+                ecs_event!(
+                    entity = *entity,
+                    component = self.component_name(component_type as ComponentType),
+                    "component added"
+                );
+            }
+        }
+        self.refresh_systems();
+        entities
+    }
+
+    /// Destroys every entity in `entities`; an id listed twice is destroyed
+    /// once. Nothing is touched unless all of them are alive.
+    pub fn despawn_batch(&mut self, entities: &[EntityType]) {
+        let mut seen = HashSet::with_capacity(entities.len());
+        let entities: Vec<EntityType> = entities
+            .iter()
+            .copied()
+            .filter(|entity| seen.insert(*entity))
+            .collect();
+        assert!(
+            entities.iter().all(|entity| self.is_alive(*entity)),
+            "Destroying an entity that is not alive."
+        );
+
+        #[cfg(feature = "tracing")]
+        for entity in entities.iter() {
+            ecs_event!(entity = *entity, "entity despawned");
+        }
+        self.cleanup_relations(&entities);
+        for entity in entities.iter() {
+            self.release_entity(*entity);
+        }
+        self.component_manager.entities_destroyed(&entities);
+        self.refresh_systems();
     }
 
     pub fn register_component<T: Any>(&mut self) {
         self.component_manager.register_component::<T>();
     }
 
+    pub fn register_tag<T: Any>(&mut self) {
+        self.component_manager.register_tag::<T>();
+    }
+
+    pub fn add_tag<T: Any>(&mut self, entity: EntityType) {
+        self.component_manager.register_tag_on_first_use::<T>();
+        assert!(
+            self.component_manager.is_tag(TypeId::of::<T>()),
+            "Adding a tag that was not registered with register_tag."
+        );
+        let component_type = self.component_manager.get_component_type::<T>();
+        self.signature_insert(entity, component_type);
+    }
+
+    pub fn remove_tag<T: Any>(&mut self, entity: EntityType) {
+        assert!(
+            self.component_manager.is_tag(TypeId::of::<T>()),
+            "Removing a tag that was not registered with register_tag."
+        );
+        let component_type = self.component_manager.get_component_type::<T>();
+        self.signature_remove(entity, component_type);
+    }
+
+    pub fn register_scene_component<T: IComponent + DeserializeOwned>(&mut self) {
+        self.component_manager.register_scene_component::<T>();
+    }
+
+    pub fn register_reflect_component<T: IComponent + Reflect>(&mut self) {
+        self.component_manager.register_reflect_component::<T>();
+    }
+
+    pub fn register_cloneable_component<T: IComponent + Clone>(&mut self) {
+        self.component_manager.register_cloneable_component::<T>();
+    }
+
+    pub fn register_destroy_hook<T: IComponent>(&mut self) {
+        self.component_manager.register_destroy_hook::<T>();
+    }
+
+    /// Registers `T` the way its `Component` impl asks for, e.g. as a tag
+    /// or as a cloneable scene component.
+    pub fn register<T: Component>(&mut self) {
+        T::register(self);
+    }
+
+    /// Creates a new entity with a copy of every cloneable component of
+    /// `src`; components that can't be cloned are left out.
+    pub fn clone_entity(&mut self, src: EntityType) -> EntityType {
+        self.clone_entity_with(src, ClonePolicy::SkipNonCloneable)
+            .expect("Skipping clone policy never fails")
+    }
+
+    pub fn clone_entity_with(
+        &mut self,
+        src: EntityType,
+        policy: ClonePolicy,
+    ) -> Result<EntityType, CloneError> {
+        let mut cloned = Vec::new();
+        for info in self.entity_components(src) {
+            match self.component_manager.clone_component(&info, src) {
+                Some(component) => cloned.push(component),
+                None if policy == ClonePolicy::ErrorOnNonCloneable => {
+                    return Err(CloneError::NotCloneable(info.name))
+                }
+                None => {}
+            }
+        }
+
+        let dst = self.create_entity();
+        self.attach_components(dst, cloned);
+        Ok(dst)
+    }
+
+    /// Copies the components in `S` from `src` onto `dst`, replacing any
+    /// `dst` already has. Components `src` doesn't have are skipped.
+    pub fn copy_components<S: ComponentSet>(&mut self, src: EntityType, dst: EntityType) {
+        S::copy(self, src, dst);
+    }
+
+    /// Whether `entity` has `T`, read from its signature so it works for
+    /// tags as well as stored components.
+    pub fn has_component<T: Any>(&self, entity: EntityType) -> bool {
+        let Some(component_type) = self
+            .component_manager
+            .registry()
+            .get(TypeId::of::<T>())
+            .map(|info| info.component_type)
+        else {
+            return false;
+        };
+        self.entity_manager
+            .signature(entity)
+            .is_some_and(|signature| signature.contains(component_type as usize))
+    }
+
+    /// Every registered component type, ordered by `ComponentType`.
+    pub fn component_infos(&self) -> Vec<ComponentInfo> {
+        self.component_manager.registry().iter().cloned().collect()
+    }
+
+    /// Components and tags attached to `entity`, ordered by `ComponentType`.
+    pub fn entity_components(&self, entity: EntityType) -> Vec<ComponentInfo> {
+        let registry = self.component_manager.registry();
+        self.entity_manager
+            .signature(entity)
+            .map(|signature| {
+                signature
+                    .iter()
+                    .filter_map(|c| registry.get_by_component_type(c as ComponentType))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn get_component_field(
+        &self,
+        entity: EntityType,
+        component: &str,
+        path: &str,
+    ) -> Result<ReflectValue, ReflectError> {
+        self.with_reflect(entity, component, |reflect| reflect.get_path(path))?
+    }
+
+    pub fn set_component_field(
+        &mut self,
+        entity: EntityType,
+        component: &str,
+        path: &str,
+        value: ReflectValue,
+    ) -> Result<(), ReflectError> {
+        self.with_reflect_mut(entity, component, |reflect| reflect.set_path(path, value))?
+    }
+
+    /// A component's reflected fields as JSON.
+    pub fn component_to_json(
+        &self,
+        entity: EntityType,
+        component: &str,
+    ) -> Result<serde_json::Value, ReflectError> {
+        self.with_reflect(entity, component, |reflect| reflect.to_json())
+    }
+
+    /// Formats a component through its reflection data.
+    pub fn debug_component(
+        &self,
+        entity: EntityType,
+        component: &str,
+    ) -> Result<String, ReflectError> {
+        self.with_reflect(entity, component, |reflect| format!("{:?}", reflect))
+    }
+
+    fn with_reflect<F, R>(
+        &self,
+        entity: EntityType,
+        component: &str,
+        f: F,
+    ) -> Result<R, ReflectError>
+    where
+        F: FnOnce(&dyn Reflect) -> R,
+    {
+        let type_id = self.reflected_type_id(component)?;
+        self.component_manager
+            .with_reflect(type_id, entity, f)
+            .ok_or_else(|| ReflectError::MissingComponent(component.to_string()))
+    }
+
+    fn with_reflect_mut<F, R>(
+        &mut self,
+        entity: EntityType,
+        component: &str,
+        f: F,
+    ) -> Result<R, ReflectError>
+    where
+        F: FnOnce(&mut dyn Reflect) -> R,
+    {
+        let type_id = self.reflected_type_id(component)?;
+        self.component_manager
+            .with_reflect_mut(type_id, entity, f)
+            .ok_or_else(|| ReflectError::MissingComponent(component.to_string()))
+    }
+
+    fn reflected_type_id(&self, component: &str) -> Result<TypeId, ReflectError> {
+        let registry = self.component_manager.registry();
+        let info = registry
+            .get_by_name(component)
+            .ok_or_else(|| ReflectError::UnknownComponent(component.to_string()))?;
+        info.type_id
+            .filter(|_| info.reflect.is_some())
+            .ok_or_else(|| ReflectError::NotReflected(component.to_string()))
+    }
+
     pub fn get_component_mut<T: Any + IComponent, F, R>(&self, entity: EntityType, f: F) -> R
     where
         F: FnOnce(&mut T) -> R,
@@ -53,38 +483,541 @@ impl EcsSingleton {
     }
 
     pub fn get_component_type<T: Any>(&mut self) -> u32 {
-        *self.component_manager.get_component_type::<T>()
+        self.component_manager.register_type_on_first_use::<T>();
+        self.component_manager.get_component_type::<T>()
+    }
+
+    /// Registers component types the first time they are inserted or
+    /// queried instead of panicking. Their ids follow first-use order until
+    /// `freeze_components` sorts them. Worlds sharing a registry can't freeze
+    /// it, so their ids stay in first-use order and differ between runs.
+    pub fn set_auto_register_components(&mut self, enabled: bool) {
+        self.shared_registry()
+            .write()
+            .unwrap()
+            .set_auto_register(enabled);
+    }
+
+    /// Renumbers every component type in type name order, so ids are the
+    /// same on every run however the types were first used, and stops
+    /// further registration. Entity and system signatures are remapped, and
+    /// every `QueryState` remaps itself on its next `query`. Component type
+    /// ids read before, e.g. from `get_component_type`, are stale.
+    ///
+    /// Fails, changing nothing, if the registry is shared, since the other
+    /// worlds' signatures couldn't be remapped.
+    pub fn freeze_components(&mut self) -> Result<(), FreezeError> {
+        let remap = self.component_manager.freeze_registry()?;
+        self.entity_manager.remap_signatures(&remap);
+        for (_, system) in self.system_manager.scheduled() {
+            system.lock().unwrap().remap_signature(&remap);
+        }
+        Ok(())
     }
 
     pub fn register_system<T: Any>(&mut self) -> TypeId {
         self.system_manager.register_system::<T>()
     }
+    pub fn unregister_system<T: Any>(&mut self) {
+        self.system_manager.unregister_system::<T>();
+    }
+
+    /// Registers a function whose arguments are system parameters, e.g.
+    /// `fn gravity(query: Query<&mut Velocity>, time: Res<Time>)`. Returns
+    /// the id to unregister it with.
+    pub fn register_system_func<M, F: SystemParamFunction<M>>(&mut self, function: F) -> TypeId {
+        let (run, access) = param::function_system(function, self);
+        self.system_manager.register_system_func::<F>(access, run)
+    }
+
+    pub fn unregister_system_by_id(&mut self, system: TypeId) {
+        self.system_manager.unregister_system_by_id(system);
+    }
+
+    /// Pairs of systems that can't run at the same time because one writes
+    /// what the other touches.
+    pub fn system_conflicts(&self) -> Vec<SystemConflict> {
+        self.system_manager.conflicts()
+    }
+
+    /// Sets what the scheduler runs for system `T` on each `run_systems`.
+    pub fn set_system_run<T: Any, F>(&mut self, mut run: F)
+    where
+        F: FnMut(&mut EcsSingleton, &[EntityType]) + Send + 'static,
+    {
+        self.system_manager.get_system::<T>().lock().unwrap().run =
+            Some(Box::new(move |singleton, entities| {
+                run(singleton, entities);
+                entities.len()
+            }));
+    }
+
+    /// Adds a condition that must hold for system `T` to run; all of a
+    /// system's conditions are checked before each run.
+    pub fn run_if<T: Any, C>(&mut self, condition: C)
+    where
+        C: Fn(&EcsSingleton) -> bool + Send + Sync + 'static,
+    {
+        self.system_manager
+            .get_system::<T>()
+            .lock()
+            .unwrap()
+            .run_conditions
+            .push(Box::new(condition));
+    }
+
+    /// A disabled system keeps its entity set up to date but is skipped by
+    /// the scheduler.
+    pub fn set_system_enabled<T: Any>(&mut self, enabled: bool) {
+        self.system_manager
+            .get_system::<T>()
+            .lock()
+            .unwrap()
+            .enabled = enabled;
+    }
+
+    pub fn is_system_enabled<T: Any>(&self) -> bool {
+        self.system_manager
+            .get_system::<T>()
+            .lock()
+            .unwrap()
+            .enabled
+    }
+
+    /// Every system in scheduling order.
+    pub fn system_infos(&self) -> Vec<SystemInfo> {
+        self.system_manager
+            .scheduled()
+            .into_iter()
+            .map(|(_, system)| {
+                let system = system.lock().unwrap();
+                SystemInfo {
+                    name: system.name,
+                    enabled: system.enabled,
+                    entities: system.entities.len(),
+                }
+            })
+            .collect()
+    }
+
+    /// Runs every enabled system whose run conditions hold, in registration
+    /// order.
+    pub fn run_systems(&mut self) {
+        for (_, system) in self.system_manager.scheduled() {
+            let Some(mut taken) = TakenRun::take(&system) else {
+                continue;
+            };
+            if !taken.conditions.iter().all(|condition| condition(self)) {
+                continue;
+            }
+
+            let (name, entities): (_, Vec<EntityType>) = {
+                let guard = system.lock().unwrap();
+                (guard.name, guard.entities.iter().copied().collect())
+            };
+            let run = taken.run.as_mut().unwrap();
+            let cursor = self.entity_manager.changes().end();
+            let start = Instant::now();
+            let matched = {
+                ecs_span!(_span, "system", name, entities = entities.len());
+                run(self, &entities)
+            };
+            let duration = start.elapsed();
+
+            if self.contains_resource::<Diagnostics>() {
+                let changes = self.changed_entities_since(cursor);
+                self.get_resource_mut::<Diagnostics>()
+                    .unwrap()
+                    .record_system(name, start, duration, matched, changes);
+            }
+        }
+    }
+
+    /// Ages the event queues, applies pending state transitions, then runs
+    /// the systems.
+    pub fn update(&mut self) {
+        ecs_span!(_span, "update");
+        let start = Instant::now();
+        {
+            ecs_span!(_span, "events");
+            for update in self.event_updates.clone() {
+                update(self);
+            }
+        }
+        {
+            ecs_span!(_span, "state_transitions");
+            for apply in self.state_transitions.clone() {
+                apply(self);
+            }
+        }
+        {
+            ecs_span!(_span, "systems");
+            self.run_systems();
+        }
+
+        if let Some(diagnostics) = self.get_resource_mut::<Diagnostics>() {
+            diagnostics.end_frame(start, start.elapsed());
+        }
+
+        if cfg!(debug_assertions) && self.validate_each_tick {
+            let violations = self.validate();
+            assert!(
+                violations.is_empty(),
+                "World invariants violated: {:?}",
+                violations
+            );
+        }
+    }
+
+    /// Cross-checks the entity free list, component storage, signatures and
+    /// system entity sets against each other. An empty list means the world
+    /// is consistent.
+    pub fn validate(&self) -> Vec<InvariantViolation> {
+        validate::validate(self)
+    }
+
+    /// In debug builds, panics at the end of any `update` that leaves the
+    /// world inconsistent. Release builds ignore this.
+    pub fn set_validate_each_tick(&mut self, enabled: bool) {
+        self.validate_each_tick = enabled;
+    }
+
+    /// Records per-system timings of the last `window` updates in a
+    /// `Diagnostics` resource.
+    pub fn enable_diagnostics(&mut self, window: usize) {
+        self.insert_resource(Diagnostics::new(window));
+    }
+
+    /// Distinct entities changed since `cursor` in the change log, or the
+    /// number of changes if part of the log was already dropped.
+    fn changed_entities_since(&self, cursor: usize) -> usize {
+        let changes = self.entity_manager.changes();
+        match changes.since(cursor) {
+            Some(changed) => {
+                let mut changed = changed.to_vec();
+                changed.sort_unstable();
+                changed.dedup();
+                changed.len()
+            }
+            None => changes.end() - cursor,
+        }
+    }
+
+    /// Adds a `State<S>` and `NextState<S>` resource, starting in `initial`.
+    /// The initial state's `OnEnter` systems run on the first `update`.
+    pub fn init_state<S: States>(&mut self, initial: S) {
+        assert!(
+            !self.contains_resource::<StateSchedules<S>>(),
+            "Initialising state more than once."
+        );
+        // Another world sharing the registry may have registered it already.
+        if !self
+            .component_manager
+            .registry()
+            .contains(TypeId::of::<StateScoped<S>>())
+        {
+            self.register_component::<StateScoped<S>>();
+        }
+        state::init_state(self, initial);
+        self.state_transitions
+            .push(state::apply_state_transition::<S>);
+    }
+
+    /// Queues a switch to `state`, applied on the next `update`.
+    pub fn set_next_state<S: States>(&mut self, state: S) {
+        self.get_resource_mut::<NextState<S>>()
+            .expect("State not initialised")
+            .set(state);
+    }
+
+    /// Attaches a system to an `OnEnter`, `OnExit` or `OnTransition` point of
+    /// the state machine.
+    pub fn add_state_system<S: States, F>(&mut self, schedule: impl StateSchedule<S>, system: F)
+    where
+        F: FnMut(&mut EcsSingleton) + Send + Sync + 'static,
+    {
+        let schedules = self
+            .get_resource_mut::<StateSchedules<S>>()
+            .expect("State not initialised");
+        schedule.add(schedules, Box::new(system));
+    }
+
+    /// Adds a `RelationIndex<R>` resource and registers `Relation<R>`.
+    /// `cleanup` decides what happens to sources when a target is destroyed.
+    pub fn register_relation<R: RelationKind>(
+        &mut self,
+        arity: RelationArity,
+        cleanup: RelationCleanup,
+    ) {
+        assert!(
+            !self.contains_resource::<RelationIndex<R>>(),
+            "Registering a relation more than once."
+        );
+        relation::init_relation::<R>(self, arity, cleanup);
+        self.relations.push(RelationHooks::of::<R>());
+    }
+
+    /// Relates `source` to `target`. For `ManyToOne` relations this replaces
+    /// the previous target of `source`.
+    pub fn add_relation<R: RelationKind>(&mut self, source: EntityType, target: EntityType) {
+        relation::add_relation::<R>(self, source, target);
+    }
+
+    pub fn remove_relation<R: RelationKind>(&mut self, source: EntityType, target: EntityType) {
+        relation::remove_relation::<R>(self, source, target);
+    }
+
+    /// Every entity related to `target` through `R`, e.g. all entities that
+    /// target it.
+    pub fn relation_sources<R: RelationKind>(&self, target: EntityType) -> &[EntityType] {
+        self.relation_index::<R>().sources(target)
+    }
+
+    pub fn relation_targets<R: RelationKind>(&self, source: EntityType) -> &[EntityType] {
+        self.relation_index::<R>().targets(source)
+    }
+
+    fn relation_index<R: RelationKind>(&self) -> &RelationIndex<R> {
+        self.get_resource::<RelationIndex<R>>()
+            .expect("Relation not registered")
+    }
+
+    /// Adds an `Events<E>` resource that is aged on every `update`.
+    pub fn add_event<E: Any + Send + Sync>(&mut self) {
+        if self.contains_resource::<Events<E>>() {
+            return;
+        }
+        self.insert_resource(Events::<E>::default());
+        self.event_updates.push(event::update_events::<E>);
+    }
+
+    pub fn send_event<E: Any + Send + Sync>(&mut self, event: E) {
+        self.get_resource_mut::<Events<E>>()
+            .expect("Event type not added")
+            .send(event);
+    }
+
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) {
+        self.resource_manager.insert(resource);
+    }
+
+    /// Inserts `R::default()` unless the resource is already present.
+    pub fn init_resource<R: Resource + Default>(&mut self) {
+        if !self.contains_resource::<R>() {
+            self.insert_resource(R::default());
+        }
+    }
+
+    pub fn remove_resource<R: Resource>(&mut self) -> Option<R> {
+        self.resource_manager.remove::<R>()
+    }
+
+    pub fn contains_resource<R: Resource>(&self) -> bool {
+        self.resource_manager.contains::<R>()
+    }
+
+    pub fn get_resource<R: Resource>(&self) -> Option<&R> {
+        self.resource_manager.get::<R>()
+    }
+
+    pub fn get_resource_mut<R: Resource>(&mut self) -> Option<&mut R> {
+        self.resource_manager.get_mut::<R>()
+    }
+
+    /// System `T`. Its entity set follows every later structural change,
+    /// so the handle can be kept.
     pub fn get_system<T: Any>(&mut self) -> &Arc<Mutex<System>> {
         self.system_manager.get_system::<T>()
     }
+    /// Snapshot of the entities currently matched by system `T`.
+    pub fn system_entities<T: Any>(&self) -> Vec<EntityType> {
+        let system = self.system_manager.get_system::<T>().lock().unwrap();
+        system.entities.iter().copied().collect()
+    }
+
+    /// Cached query for use outside systems. Keep the state around: later
+    /// calls to `QueryState::query` only replay what changed in between.
+    pub fn query<Q: QueryData>(&mut self) -> QueryState<Q> {
+        QueryState::new(self)
+    }
+
     pub fn set_system_signature<T: Any>(&mut self, signature: Signature) {
-        self.system_manager.set_signatures::<T>(signature)
+        self.system_manager.set_signatures::<T>(signature);
+        self.refresh_systems();
     }
 
     pub fn add_component<T: Any + IComponent>(&mut self, entity: EntityType, component: T) {
+        self.component_manager.register_on_first_use::<T>();
+        if self.component_manager.is_tag(TypeId::of::<T>()) {
+            return self.add_tag::<T>(entity);
+        }
         self.component_manager.add_component::<T>(entity, component);
         let component_type = self.component_manager.get_component_type::<T>();
+        self.signature_insert(entity, component_type);
+    }
 
-        let mut signature: Signature = Default::default();
-        signature.insert(*component_type as usize);
-        self.entity_manager.set_signature(entity, signature);
+    pub fn register_dynamic_component(
+        &mut self,
+        name: &str,
+        layout: Layout,
+        drop_fn: Option<DynamicDropFn>,
+    ) -> ComponentType {
+        self.component_manager
+            .register_dynamic_component(name, layout, drop_fn)
+    }
 
-        let signature = self.entity_manager.get_signature(entity);
-        self.system_manager
-            .entity_signature_changed(entity, &signature);
+    pub fn get_component_type_by_name(&self, name: &str) -> Option<ComponentType> {
+        self.component_manager
+            .registry()
+            .get_by_name(name)
+            .map(|info| info.component_type)
+    }
+
+    pub fn add_dynamic_component(
+        &mut self,
+        entity: EntityType,
+        component_type: ComponentType,
+        bytes: &[u8],
+    ) {
+        self.component_manager
+            .add_dynamic_component(component_type, entity, bytes);
+        self.signature_insert(entity, component_type);
+    }
+
+    pub fn remove_dynamic_component(&mut self, entity: EntityType, component_type: ComponentType) {
+        self.component_manager
+            .remove_dynamic_component(component_type, entity);
+        self.signature_remove(entity, component_type);
+    }
+
+    pub fn get_dynamic_component<F, R>(
+        &self,
+        entity: EntityType,
+        component_type: ComponentType,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.component_manager
+            .get_dynamic_component(component_type, entity, f)
+    }
+
+    pub fn get_dynamic_component_mut<F, R>(
+        &self,
+        entity: EntityType,
+        component_type: ComponentType,
+        f: F,
+    ) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.component_manager
+            .get_dynamic_component_mut(component_type, entity, f)
+    }
+
+    pub(crate) fn add_component_boxed(
+        &mut self,
+        type_id: TypeId,
+        entity: EntityType,
+        component: BoxedComponent,
+    ) {
+        self.component_manager
+            .add_component_boxed(type_id, entity, component);
+        let component_type = self.component_manager.get_component_type_by_id(type_id);
+        self.signature_insert(entity, component_type);
     }
 
     pub fn remove_component<T: Any + IComponent>(&mut self, entity: EntityType) {
+        if self.component_manager.is_tag(TypeId::of::<T>()) {
+            return self.remove_tag::<T>(entity);
+        }
+        if let Some(hooks) = self
+            .relations
+            .iter()
+            .find(|hooks| hooks.type_id == TypeId::of::<T>())
+        {
+            (hooks.component_removed)(self, entity);
+        }
         self.component_manager.remove_component::<T>(entity);
-        self.entity_manager.remove_signature(entity);
+        let component_type = self.component_manager.get_component_type::<T>();
+        self.signature_remove(entity, component_type);
+    }
+
+    /// Spawns every entity of `scene` with fresh ids, returned in scene order.
+    pub fn instantiate(&mut self, scene: &Scene) -> Result<Vec<EntityType>, SceneError> {
+        self.instantiate_with(scene, &SceneOverrides::new())
+    }
+
+    pub fn instantiate_with(
+        &mut self,
+        scene: &Scene,
+        overrides: &SceneOverrides,
+    ) -> Result<Vec<EntityType>, SceneError> {
+        let prepared = scene.prepare(&self.component_manager, overrides)?;
+
+        let entities: Vec<EntityType> = prepared.iter().map(|_| self.create_entity()).collect();
+        let mut children: Vec<Vec<EntityType>> = vec![Vec::new(); entities.len()];
+
+        for (index, prepared_entity) in prepared.into_iter().enumerate() {
+            let entity = entities[index];
+            for (type_id, component) in prepared_entity.components {
+                self.add_component_boxed(type_id, entity, component);
+            }
+            if let Some(parent) = prepared_entity.parent {
+                self.add_component(entity, Parent(entities[parent]));
+                children[parent].push(entity);
+            }
+        }
+
+        for (index, children) in children.into_iter().enumerate() {
+            if !children.is_empty() {
+                self.add_component(entities[index], Children(children));
+            }
+        }
+        Ok(entities)
+    }
+
+    fn signature_insert(&mut self, entity: EntityType, component_type: ComponentType) {
+        ecs_event!(
+            entity,
+            component = self.component_name(component_type),
+            "component added"
+        );
         let signature = self.entity_manager.get_signature(entity);
+        if signature.insert(component_type as usize) {
+            self.entity_manager.record_change(entity);
+            self.refresh_systems();
+        }
+    }
 
-        self.system_manager
-            .entity_signature_changed(entity, &signature)
+    fn signature_remove(&mut self, entity: EntityType, component_type: ComponentType) {
+        ecs_event!(
+            entity,
+            component = self.component_name(component_type),
+            "component removed"
+        );
+        let signature = self.entity_manager.get_signature(entity);
+        if signature.remove(component_type as usize) {
+            self.entity_manager.record_change(entity);
+            self.refresh_systems();
+        }
+    }
+
+    /// Replays the structural changes made since the last call into every
+    /// system's entity set, keeping them current for code that holds a
+    /// system between changes.
+    fn refresh_systems(&self) {
+        for system in self.system_manager.iter() {
+            system.lock().unwrap().refresh(&self.entity_manager);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn component_name(&self, component_type: ComponentType) -> String {
+        self.component_manager
+            .registry()
+            .get_by_component_type(component_type)
+            .map_or_else(|| component_type.to_string(), |info| info.name.clone())
     }
 }
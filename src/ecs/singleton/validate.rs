@@ -0,0 +1,188 @@
+use std::collections::HashSet;
+
+use crate::ecs::ecs::{
+    component::StorageProblem,
+    config::{EntityType, MAX_ENTITIES},
+    singleton::EcsSingleton,
+};
+
+/// One broken invariant found by `EcsSingleton::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// An id is in the free list more than once.
+    FreeListDuplicate(EntityType),
+    /// An id is in the free list while it still has a signature.
+    FreeEntityAlive(EntityType),
+    /// The living counter disagrees with the entities that have a signature.
+    LivingCountMismatch { living: u32, alive: usize },
+    /// Free and living ids don't add up to `MAX_ENTITIES`.
+    EntityCountMismatch { free: usize, living: u32 },
+    /// A component array's index maps, size or slots disagree.
+    Storage {
+        component: String,
+        problem: StorageProblem,
+    },
+    /// An entity's signature and a component array disagree about whether
+    /// the entity has that component.
+    SignatureMismatch {
+        component: String,
+        entity: EntityType,
+        in_signature: bool,
+        in_storage: bool,
+    },
+    /// A system's cached entity set disagrees with matching its signature
+    /// from scratch.
+    SystemEntitiesMismatch {
+        system: &'static str,
+        entity: EntityType,
+        cached: bool,
+        expected: bool,
+    },
+}
+
+pub(crate) fn validate(singleton: &EcsSingleton) -> Vec<InvariantViolation> {
+    let mut violations = Vec::new();
+    check_entities(singleton, &mut violations);
+    check_components(singleton, &mut violations);
+    check_systems(singleton, &mut violations);
+    violations
+}
+
+fn check_entities(singleton: &EcsSingleton, violations: &mut Vec<InvariantViolation>) {
+    let entity_manager = singleton.entity_manager();
+    let mut free = HashSet::new();
+    for entity in entity_manager.free_entities() {
+        if !free.insert(entity) {
+            violations.push(InvariantViolation::FreeListDuplicate(entity));
+        }
+        if entity_manager.is_alive(entity) {
+            violations.push(InvariantViolation::FreeEntityAlive(entity));
+        }
+    }
+
+    let living = entity_manager.living();
+    let alive = entity_manager.iter().count();
+    if alive != living as usize {
+        violations.push(InvariantViolation::LivingCountMismatch { living, alive });
+    }
+    let free = entity_manager.free_entities().count();
+    if free + living as usize != MAX_ENTITIES as usize {
+        violations.push(InvariantViolation::EntityCountMismatch { free, living });
+    }
+}
+
+fn check_components(singleton: &EcsSingleton, violations: &mut Vec<InvariantViolation>) {
+    let entity_manager = singleton.entity_manager();
+    for report in singleton.component_manager().check_storage() {
+        let component = &report.info.name;
+        for problem in report.problems {
+            violations.push(InvariantViolation::Storage {
+                component: component.clone(),
+                problem,
+            });
+        }
+
+        let component_type = report.info.component_type as usize;
+        let stored: HashSet<EntityType> = report.entities.into_iter().collect();
+        let signed: HashSet<EntityType> = entity_manager
+            .iter()
+            .filter(|(_, signature)| signature.contains(component_type))
+            .map(|(entity, _)| entity)
+            .collect();
+
+        let mut mismatched: Vec<EntityType> =
+            stored.symmetric_difference(&signed).copied().collect();
+        mismatched.sort_unstable();
+        for entity in mismatched {
+            violations.push(InvariantViolation::SignatureMismatch {
+                component: component.clone(),
+                entity,
+                in_signature: signed.contains(&entity),
+                in_storage: stored.contains(&entity),
+            });
+        }
+    }
+}
+
+fn check_systems(singleton: &EcsSingleton, violations: &mut Vec<InvariantViolation>) {
+    let entity_manager = singleton.entity_manager();
+    for (_, system) in singleton.system_manager().scheduled() {
+        let system = system.lock().unwrap();
+        let Some(signature) = system.signature().cloned() else {
+            continue;
+        };
+
+        let expected: HashSet<EntityType> = entity_manager
+            .iter()
+            .filter(|(_, entity_signature)| signature.is_subset(entity_signature))
+            .map(|(entity, _)| entity)
+            .collect();
+        let mut mismatched: Vec<EntityType> = system
+            .entities
+            .symmetric_difference(&expected)
+            .copied()
+            .collect();
+        mismatched.sort_unstable();
+        for entity in mismatched {
+            violations.push(InvariantViolation::SystemEntitiesMismatch {
+                system: system.name,
+                entity,
+                cached: system.entities.contains(&entity),
+                expected: expected.contains(&entity),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod validate {
+    use super::*;
+    use crate::ecs::ecs::component::IComponent;
+
+    struct Health(i32);
+
+    impl IComponent for Health {
+        fn entity_destroyed(&mut self, _entity: EntityType) {}
+    }
+
+    struct Regen;
+
+    #[test]
+    fn test_valid_world_has_no_violations() {
+        let mut singleton = EcsSingleton::new();
+        singleton.register_component::<Health>();
+        singleton.register_system::<Regen>();
+        let mut signature = crate::ecs::ecs::config::Signature::default();
+        signature.insert(singleton.get_component_type::<Health>() as usize);
+        singleton.set_system_signature::<Regen>(signature);
+
+        let entities = singleton.spawn_batch((0..4).map(|i| (Health(i),)));
+        singleton.destroy_entity(entities[1]);
+        singleton.remove_component::<Health>(entities[2]);
+
+        assert_eq!(singleton.get_component::<Health>(entities[3]).0, 3);
+        assert_eq!(singleton.validate(), []);
+    }
+
+    #[test]
+    fn test_storage_out_of_sync_with_signature() {
+        let mut singleton = EcsSingleton::new();
+        singleton.register_component::<Health>();
+        let entity = singleton.create_entity();
+        singleton.add_component(entity, Health(10));
+
+        singleton
+            .component_manager_mut()
+            .remove_component::<Health>(entity);
+
+        assert_eq!(
+            singleton.validate(),
+            [InvariantViolation::SignatureMismatch {
+                component: "Health".to_string(),
+                entity,
+                in_signature: true,
+                in_storage: false,
+            }]
+        );
+    }
+}
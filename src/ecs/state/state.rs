@@ -0,0 +1,200 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
+
+use crate::ecs::ecs::{
+    component::IComponent, config::EntityType, resource_manager::Resource, singleton::EcsSingleton,
+};
+
+/// A value usable as a game state, e.g. `enum GameState { Menu, Playing }`.
+pub trait States: Clone + PartialEq + Eq + Hash + Debug + Send + Sync + 'static {}
+
+impl<T: Clone + PartialEq + Eq + Hash + Debug + Send + Sync + 'static> States for T {}
+
+/// The current state, stored as a resource by `EcsSingleton::init_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State<S: States>(S);
+
+impl<S: States> Resource for State<S> {}
+
+impl<S: States> State<S> {
+    pub fn get(&self) -> &S {
+        &self.0
+    }
+}
+
+/// The state to switch to on the next `EcsSingleton::update`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextState<S: States>(Option<S>);
+
+impl<S: States> Resource for NextState<S> {}
+
+impl<S: States> NextState<S> {
+    pub fn set(&mut self, state: S) {
+        self.0 = Some(state);
+    }
+}
+
+/// Runs when the state is entered.
+pub struct OnEnter<S: States>(pub S);
+
+/// Runs when the state is left, before `OnTransition` and `OnEnter`.
+pub struct OnExit<S: States>(pub S);
+
+/// Runs when moving from `from` to `to`, between `OnExit` and `OnEnter`.
+pub struct OnTransition<S: States> {
+    pub from: S,
+    pub to: S,
+}
+
+/// Marks an entity that is despawned when the world leaves the given state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateScoped<S: States>(pub S);
+
+impl<S: States> IComponent for StateScoped<S> {
+    fn entity_destroyed(&mut self, _entity: EntityType) {}
+}
+
+pub type StateSystem = Box<dyn FnMut(&mut EcsSingleton) + Send + Sync>;
+
+/// Callbacks registered for each transition of `S`, kept as a resource.
+pub struct StateSchedules<S: States> {
+    on_enter: HashMap<S, Vec<StateSystem>>,
+    on_exit: HashMap<S, Vec<StateSystem>>,
+    on_transition: HashMap<(S, S), Vec<StateSystem>>,
+    entered: bool,
+}
+
+impl<S: States> Resource for StateSchedules<S> {}
+
+impl<S: States> Default for StateSchedules<S> {
+    fn default() -> Self {
+        Self {
+            on_enter: HashMap::new(),
+            on_exit: HashMap::new(),
+            on_transition: HashMap::new(),
+            entered: false,
+        }
+    }
+}
+
+/// A point in the state machine that systems can be attached to.
+pub trait StateSchedule<S: States> {
+    fn add(self, schedules: &mut StateSchedules<S>, system: StateSystem);
+}
+
+impl<S: States> StateSchedule<S> for OnEnter<S> {
+    fn add(self, schedules: &mut StateSchedules<S>, system: StateSystem) {
+        schedules.on_enter.entry(self.0).or_default().push(system);
+    }
+}
+
+impl<S: States> StateSchedule<S> for OnExit<S> {
+    fn add(self, schedules: &mut StateSchedules<S>, system: StateSystem) {
+        schedules.on_exit.entry(self.0).or_default().push(system);
+    }
+}
+
+impl<S: States> StateSchedule<S> for OnTransition<S> {
+    fn add(self, schedules: &mut StateSchedules<S>, system: StateSystem) {
+        schedules
+            .on_transition
+            .entry((self.from, self.to))
+            .or_default()
+            .push(system);
+    }
+}
+
+/// Run condition for `EcsSingleton::run_if`.
+pub fn in_state<S: States>(state: S) -> impl Fn(&EcsSingleton) -> bool + Send + Sync {
+    move |singleton| {
+        singleton
+            .get_resource::<State<S>>()
+            .is_some_and(|current| current.0 == state)
+    }
+}
+
+pub(crate) fn init_state<S: States>(singleton: &mut EcsSingleton, initial: S) {
+    singleton.insert_resource(State(initial));
+    singleton.insert_resource(NextState::<S>(None));
+    singleton.insert_resource(StateSchedules::<S>::default());
+}
+
+/// Applies a pending `NextState<S>`: runs `OnExit`, despawns entities scoped
+/// to the old state, runs `OnTransition`, swaps `State<S>` and runs `OnEnter`.
+/// The first call enters the initial state.
+pub(crate) fn apply_state_transition<S: States>(singleton: &mut EcsSingleton) {
+    let current = singleton
+        .get_resource::<State<S>>()
+        .expect("State not initialised")
+        .0
+        .clone();
+
+    let schedules = singleton
+        .get_resource_mut::<StateSchedules<S>>()
+        .expect("State not initialised");
+    if !schedules.entered {
+        schedules.entered = true;
+        run_systems(
+            singleton,
+            |s: &mut StateSchedules<S>| &mut s.on_enter,
+            &current,
+        );
+    }
+
+    let next = singleton
+        .get_resource_mut::<NextState<S>>()
+        .and_then(|next| next.0.take());
+    let Some(next) = next.filter(|next| *next != current) else {
+        return;
+    };
+
+    run_systems(
+        singleton,
+        |s: &mut StateSchedules<S>| &mut s.on_exit,
+        &current,
+    );
+    let scoped: Vec<EntityType> = singleton
+        .entities()
+        .map(|(entity, _)| entity)
+        .filter(|entity| {
+            singleton.has_component::<StateScoped<S>>(*entity)
+                && singleton.get_component::<StateScoped<S>>(*entity).0 == current
+        })
+        .collect();
+    singleton.despawn_batch(&scoped);
+    run_systems(
+        singleton,
+        |s: &mut StateSchedules<S>| &mut s.on_transition,
+        &(current, next.clone()),
+    );
+    singleton.insert_resource(State(next.clone()));
+    run_systems(
+        singleton,
+        |s: &mut StateSchedules<S>| &mut s.on_enter,
+        &next,
+    );
+}
+
+/// Takes the systems for `key` out of the schedules while they run, so they
+/// can borrow the world mutably, then puts them back in front of any systems
+/// added meanwhile.
+fn run_systems<S: States, K: Eq + Hash + Clone>(
+    singleton: &mut EcsSingleton,
+    map: fn(&mut StateSchedules<S>) -> &mut HashMap<K, Vec<StateSystem>>,
+    key: &K,
+) {
+    let Some(mut systems) = singleton
+        .get_resource_mut::<StateSchedules<S>>()
+        .and_then(|schedules| map(schedules).remove(key))
+    else {
+        return;
+    };
+    for system in systems.iter_mut() {
+        system(singleton);
+    }
+    let schedules = singleton
+        .get_resource_mut::<StateSchedules<S>>()
+        .expect("State not initialised");
+    let added = map(schedules).entry(key.clone()).or_default();
+    systems.append(added);
+    *added = systems;
+}
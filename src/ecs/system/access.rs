@@ -0,0 +1,128 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+};
+
+/// The components and resources a system reads and writes, collected from
+/// its parameters when it is registered. The two are tracked apart, since a
+/// type may be used both as a component and as a resource.
+#[derive(Debug, Clone, Default)]
+pub struct Access {
+    components: AccessSet,
+    resources: AccessSet,
+    exclusive: bool,
+}
+
+impl Access {
+    /// Access of a system that takes the whole world and may touch anything.
+    pub fn exclusive() -> Self {
+        Self {
+            exclusive: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    /// Records a read of component `T`. Each component may only be accessed
+    /// once per system, since its storage is locked for the whole run.
+    pub fn add_read<T: Any>(&mut self) {
+        self.components.add_read::<T>();
+    }
+
+    pub fn add_write<T: Any>(&mut self) {
+        self.components.add_write::<T>();
+    }
+
+    /// Records a read of resource `R`. Each resource may only be accessed
+    /// once per system, since it is taken out of the world for the run.
+    pub fn add_resource_read<R: Any>(&mut self) {
+        self.resources.add_read::<R>();
+    }
+
+    pub fn add_resource_write<R: Any>(&mut self) {
+        self.resources.add_write::<R>();
+    }
+
+    /// Components and resources read, by type name.
+    pub fn reads(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.components
+            .reads
+            .values()
+            .chain(self.resources.reads.values())
+            .copied()
+    }
+
+    /// Components and resources written, by type name.
+    pub fn writes(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.components
+            .writes
+            .values()
+            .chain(self.resources.writes.values())
+            .copied()
+    }
+
+    /// Names of the components and resources both systems touch where at
+    /// least one of them writes. Empty when the two can run in either order.
+    pub fn conflicts(&self, other: &Access) -> Vec<&'static str> {
+        if self.exclusive || other.exclusive {
+            return vec!["EcsSingleton"];
+        }
+        let mut conflicts = self.components.conflicts(&other.components);
+        conflicts.extend(self.resources.conflicts(&other.resources));
+        conflicts.sort_unstable();
+        conflicts
+    }
+}
+
+/// Reads and writes of one kind of data, keyed by type.
+#[derive(Debug, Clone, Default)]
+struct AccessSet {
+    reads: HashMap<TypeId, &'static str>,
+    writes: HashMap<TypeId, &'static str>,
+}
+
+impl AccessSet {
+    fn add_read<T: Any>(&mut self) {
+        self.assert_unused::<T>();
+        self.reads.insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    fn add_write<T: Any>(&mut self) {
+        self.assert_unused::<T>();
+        self.writes.insert(TypeId::of::<T>(), type_name::<T>());
+    }
+
+    fn conflicts(&self, other: &AccessSet) -> Vec<&'static str> {
+        self.writes
+            .iter()
+            .filter(|(id, _)| other.reads.contains_key(id) || other.writes.contains_key(id))
+            .chain(
+                other
+                    .writes
+                    .iter()
+                    .filter(|(id, _)| self.reads.contains_key(id)),
+            )
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    fn assert_unused<T: Any>(&self) {
+        let type_id = TypeId::of::<T>();
+        assert!(
+            !self.reads.contains_key(&type_id) && !self.writes.contains_key(&type_id),
+            "System accesses {} more than once",
+            type_name::<T>()
+        );
+    }
+}
+
+/// Two systems whose access overlaps, in scheduling order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemConflict {
+    pub first: &'static str,
+    pub second: &'static str,
+    pub on: Vec<&'static str>,
+}
@@ -0,0 +1,21 @@
+use crate::ecs::ecs::{resource_manager::Resource, singleton::EcsSingleton};
+
+/// Decides, right before a scheduled run, whether a system runs this tick.
+pub type RunCondition = Box<dyn Fn(&EcsSingleton) -> bool + Send + Sync>;
+
+pub fn resource_exists<R: Resource>() -> impl Fn(&EcsSingleton) -> bool + Send + Sync {
+    |singleton| singleton.contains_resource::<R>()
+}
+
+pub fn resource_equals<R: Resource + PartialEq>(
+    value: R,
+) -> impl Fn(&EcsSingleton) -> bool + Send + Sync {
+    move |singleton| singleton.get_resource::<R>() == Some(&value)
+}
+
+pub fn not<C>(condition: C) -> impl Fn(&EcsSingleton) -> bool + Send + Sync
+where
+    C: Fn(&EcsSingleton) -> bool + Send + Sync,
+{
+    move |singleton| !condition(singleton)
+}
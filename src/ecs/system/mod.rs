@@ -1,2 +1,6 @@
+pub mod access;
+pub mod condition;
+pub mod param;
+pub mod query;
 pub mod system;
 pub mod system_manager;
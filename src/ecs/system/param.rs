@@ -0,0 +1,321 @@
+use std::{
+    any::type_name,
+    ops::{Deref, DerefMut},
+};
+
+use crate::ecs::ecs::{
+    bundle::Bundle, component::IComponent, config::EntityType, resource_manager::Resource,
+    singleton::EcsSingleton,
+};
+
+use super::{access::Access, system::SystemRun};
+
+/// Something a function system can take as an argument.
+///
+/// `init` runs once at registration and declares the parameter's access.
+/// Around every run, `prepare` moves what the parameter needs out of the
+/// world, `fetch` lends it to the system and `apply` gives it back, so
+/// parameters never alias each other or the world. `apply_deferred` runs
+/// last, once every parameter has given back what it took, to carry out
+/// changes queued during the run.
+pub trait SystemParam {
+    type State: Send + 'static;
+    type Item<'w>;
+
+    fn init(singleton: &mut EcsSingleton, access: &mut Access) -> Self::State;
+
+    fn prepare(_state: &mut Self::State, _singleton: &mut EcsSingleton) {}
+
+    fn fetch<'w>(state: &'w mut Self::State, singleton: &'w EcsSingleton) -> Self::Item<'w>;
+
+    fn apply(_state: &mut Self::State, _singleton: &mut EcsSingleton) {}
+
+    fn apply_deferred(_state: &mut Self::State, _singleton: &mut EcsSingleton) {}
+
+    /// Entities the parameter matched in the last run, for diagnostics.
+    fn matched(_state: &Self::State) -> usize {
+        0
+    }
+}
+
+pub type SystemParamItem<'w, P> = <P as SystemParam>::Item<'w>;
+
+/// Shared access to a resource.
+pub struct Res<'w, R: Resource>(&'w R);
+
+impl<R: Resource> Deref for Res<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        self.0
+    }
+}
+
+/// Exclusive access to a resource.
+pub struct ResMut<'w, R: Resource>(&'w mut R);
+
+impl<R: Resource> Deref for ResMut<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &R {
+        self.0
+    }
+}
+
+impl<R: Resource> DerefMut for ResMut<'_, R> {
+    fn deref_mut(&mut self) -> &mut R {
+        self.0
+    }
+}
+
+pub(crate) fn take_resource<R: Resource>(state: &mut Option<R>, singleton: &mut EcsSingleton) {
+    *state = Some(
+        singleton
+            .remove_resource::<R>()
+            .unwrap_or_else(|| panic!("Resource {} not found", type_name::<R>())),
+    );
+}
+
+pub(crate) fn return_resource<R: Resource>(state: &mut Option<R>, singleton: &mut EcsSingleton) {
+    if let Some(resource) = state.take() {
+        singleton.insert_resource(resource);
+    }
+}
+
+impl<R: Resource> SystemParam for Res<'_, R> {
+    type State = Option<R>;
+    type Item<'w> = Res<'w, R>;
+
+    fn init(_singleton: &mut EcsSingleton, access: &mut Access) -> Self::State {
+        access.add_resource_read::<R>();
+        None
+    }
+
+    fn prepare(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        take_resource(state, singleton);
+    }
+
+    fn fetch<'w>(state: &'w mut Self::State, _singleton: &'w EcsSingleton) -> Self::Item<'w> {
+        Res(state.as_ref().unwrap())
+    }
+
+    fn apply(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        return_resource(state, singleton);
+    }
+}
+
+impl<R: Resource> SystemParam for ResMut<'_, R> {
+    type State = Option<R>;
+    type Item<'w> = ResMut<'w, R>;
+
+    fn init(_singleton: &mut EcsSingleton, access: &mut Access) -> Self::State {
+        access.add_resource_write::<R>();
+        None
+    }
+
+    fn prepare(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        take_resource(state, singleton);
+    }
+
+    fn fetch<'w>(state: &'w mut Self::State, _singleton: &'w EcsSingleton) -> Self::Item<'w> {
+        ResMut(state.as_mut().unwrap())
+    }
+
+    fn apply(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        return_resource(state, singleton);
+    }
+}
+
+type Command = Box<dyn FnOnce(&mut EcsSingleton) + Send>;
+
+/// Structural changes queued by a system and applied once it returns and
+/// its other parameters have put their resources back.
+pub struct Commands<'w> {
+    queue: &'w mut Vec<Command>,
+}
+
+impl Commands<'_> {
+    /// Queues an arbitrary change to the world.
+    pub fn add(&mut self, command: impl FnOnce(&mut EcsSingleton) + Send + 'static) {
+        self.queue.push(Box::new(command));
+    }
+
+    pub fn spawn<B: Bundle + Send + 'static>(&mut self, bundle: B) {
+        self.add(move |singleton| {
+            singleton.spawn_batch([bundle]);
+        });
+    }
+
+    pub fn despawn(&mut self, entity: EntityType) {
+        self.add(move |singleton| singleton.destroy_entity(entity));
+    }
+
+    pub fn add_component<T: IComponent>(&mut self, entity: EntityType, component: T) {
+        self.add(move |singleton| singleton.add_component(entity, component));
+    }
+
+    pub fn remove_component<T: IComponent>(&mut self, entity: EntityType) {
+        self.add(move |singleton| singleton.remove_component::<T>(entity));
+    }
+
+    pub fn insert_resource<R: Resource>(&mut self, resource: R) {
+        self.add(move |singleton| singleton.insert_resource(resource));
+    }
+}
+
+impl SystemParam for Commands<'_> {
+    type State = Vec<Command>;
+    type Item<'w> = Commands<'w>;
+
+    fn init(_singleton: &mut EcsSingleton, _access: &mut Access) -> Self::State {
+        Vec::new()
+    }
+
+    /// Drops whatever a run that panicked left queued.
+    fn prepare(state: &mut Self::State, _singleton: &mut EcsSingleton) {
+        state.clear();
+    }
+
+    fn fetch<'w>(state: &'w mut Self::State, _singleton: &'w EcsSingleton) -> Self::Item<'w> {
+        Commands { queue: state }
+    }
+
+    fn apply_deferred(state: &mut Self::State, singleton: &mut EcsSingleton) {
+        for command in state.drain(..) {
+            command(singleton);
+        }
+    }
+}
+
+macro_rules! impl_system_param {
+    ($($param:ident $state:ident),*) => {
+        impl<$($param: SystemParam),*> SystemParam for ($($param,)*) {
+            type State = ($($param::State,)*);
+            type Item<'w> = ($($param::Item<'w>,)*);
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn init(singleton: &mut EcsSingleton, access: &mut Access) -> Self::State {
+                ($($param::init(singleton, access),)*)
+            }
+
+            #[allow(unused_variables)]
+            fn prepare(state: &mut Self::State, singleton: &mut EcsSingleton) {
+                let ($($state,)*) = state;
+                $($param::prepare($state, singleton);)*
+            }
+
+            #[allow(unused_variables, clippy::unused_unit)]
+            fn fetch<'w>(state: &'w mut Self::State, singleton: &'w EcsSingleton) -> Self::Item<'w> {
+                let ($($state,)*) = state;
+                ($($param::fetch($state, singleton),)*)
+            }
+
+            #[allow(unused_variables)]
+            fn apply(state: &mut Self::State, singleton: &mut EcsSingleton) {
+                let ($($state,)*) = state;
+                $($param::apply($state, singleton);)*
+            }
+
+            #[allow(unused_variables)]
+            fn apply_deferred(state: &mut Self::State, singleton: &mut EcsSingleton) {
+                let ($($state,)*) = state;
+                $($param::apply_deferred($state, singleton);)*
+            }
+
+            #[allow(unused_variables)]
+            fn matched(state: &Self::State) -> usize {
+                let ($($state,)*) = state;
+                0 $(+ $param::matched($state))*
+            }
+        }
+    };
+}
+
+impl_system_param!();
+impl_system_param!(A a);
+impl_system_param!(A a, B b);
+impl_system_param!(A a, B b, C c);
+impl_system_param!(A a, B b, C c, D d);
+impl_system_param!(A a, B b, C c, D d, E e);
+impl_system_param!(A a, B b, C c, D d, E e, F f);
+impl_system_param!(A a, B b, C c, D d, E e, F f, G g);
+impl_system_param!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// A plain function usable as a system: every argument is a `SystemParam`.
+/// `Marker` only tells the impls for different arities apart.
+pub trait SystemParamFunction<Marker>: Send + 'static {
+    type Param: SystemParam;
+
+    fn run(&mut self, params: SystemParamItem<'_, Self::Param>);
+}
+
+macro_rules! impl_system_param_function {
+    ($($param:ident $arg:ident),*) => {
+        impl<Func, $($param: SystemParam),*> SystemParamFunction<fn($($param,)*)> for Func
+        where
+            Func: Send + 'static,
+            for<'a> &'a mut Func:
+                FnMut($($param),*) + FnMut($(SystemParamItem<$param>),*),
+        {
+            type Param = ($($param,)*);
+
+            #[allow(unused_variables)]
+            fn run(&mut self, params: SystemParamItem<'_, Self::Param>) {
+                // Calling through a generic function lets the compiler pick
+                // the `FnMut(Item<'w>)` signature over `FnMut(P)`.
+                #[allow(clippy::too_many_arguments)]
+                fn call<$($param),*>(mut f: impl FnMut($($param),*), $($arg: $param),*) {
+                    f($($arg),*)
+                }
+                let ($($arg,)*) = params;
+                call(self, $($arg),*)
+            }
+        }
+    };
+}
+
+impl_system_param_function!();
+impl_system_param_function!(A a);
+impl_system_param_function!(A a, B b);
+impl_system_param_function!(A a, B b, C c);
+impl_system_param_function!(A a, B b, C c, D d);
+impl_system_param_function!(A a, B b, C c, D d, E e);
+impl_system_param_function!(A a, B b, C c, D d, E e, F f);
+impl_system_param_function!(A a, B b, C c, D d, E e, F f, G g);
+impl_system_param_function!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Wraps a function system into what the scheduler calls, initialising its
+/// parameters and returning their combined access.
+pub(crate) fn function_system<M, F: SystemParamFunction<M>>(
+    mut function: F,
+    singleton: &mut EcsSingleton,
+) -> (SystemRun, Access) {
+    let mut access = Access::default();
+    let mut state = F::Param::init(singleton, &mut access);
+    let run: SystemRun = Box::new(move |singleton, _entities| {
+        let guard = ApplyGuard::<F::Param> {
+            state: &mut state,
+            singleton: &mut *singleton,
+        };
+        F::Param::prepare(&mut *guard.state, &mut *guard.singleton);
+        function.run(F::Param::fetch(&mut *guard.state, &*guard.singleton));
+        drop(guard);
+        F::Param::apply_deferred(&mut state, singleton);
+        F::Param::matched(&state)
+    });
+    (run, access)
+}
+
+/// Calls `apply` when dropped, so resources taken by `prepare` go back to
+/// the world even if the system panics or a later parameter's resource is
+/// missing.
+struct ApplyGuard<'a, P: SystemParam> {
+    state: &'a mut P::State,
+    singleton: &'a mut EcsSingleton,
+}
+
+impl<P: SystemParam> Drop for ApplyGuard<'_, P> {
+    fn drop(&mut self) {
+        P::apply(self.state, self.singleton);
+    }
+}
@@ -0,0 +1,433 @@
+use std::{
+    any::{type_name, TypeId},
+    marker::PhantomData,
+    sync::{RwLockReadGuard, RwLockWriteGuard},
+};
+
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use crate::ecs::ecs::{
+    component::{ComponentArray, IComponent},
+    component_manager::ComponentManager,
+    config::{ComponentType, EntityType, Signature},
+    entity_manager::EntityManager,
+    registry::remap_signature,
+    resource_manager::Resource,
+    singleton::EcsSingleton,
+};
+
+use super::{access::Access, param::SystemParam};
+
+/// What a `Query` yields per entity: `&T`, `&mut T` or a tuple of those.
+pub trait QueryData {
+    type Fetch<'w>;
+    type Item<'a>;
+
+    fn init(
+        component_manager: &mut ComponentManager,
+        signature: &mut Signature,
+        access: &mut Access,
+    );
+
+    fn fetch(component_manager: &ComponentManager) -> Self::Fetch<'_>;
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: EntityType) -> Self::Item<'a>;
+
+    /// Items of all `entities` at once, each borrowing disjoint storage.
+    fn get_many<'a>(fetch: &'a mut Self::Fetch<'_>, entities: &[EntityType])
+        -> Vec<Self::Item<'a>>;
+}
+
+/// `QueryData` that only reads, so its queries can run while other readers
+/// hold the same component arrays.
+///
+/// # Safety
+///
+/// `fetch` must take only shared locks and `Item` must give no mutable
+/// access to world data.
+pub unsafe trait ReadOnlyQueryData: QueryData {}
+
+/// The read-locked storage of one component type, held while a `Query` lives.
+pub struct ColumnRef<'w>(RwLockReadGuard<'w, ComponentArray>);
+
+/// The write-locked storage of one component type, held while a `Query` lives.
+pub struct ColumnMut<'w>(RwLockWriteGuard<'w, ComponentArray>);
+
+fn init_component<T: IComponent>(
+    component_manager: &mut ComponentManager,
+    signature: &mut Signature,
+) {
+    let type_id = TypeId::of::<T>();
+    assert!(
+        !component_manager.is_tag(type_id),
+        "Tag {} can't be queried by reference",
+        type_name::<T>()
+    );
+    component_manager.ensure_array::<T>();
+    signature.insert(component_manager.get_component_type_by_id(type_id) as usize);
+}
+
+impl<T: IComponent> QueryData for &T {
+    type Fetch<'w> = ColumnRef<'w>;
+    type Item<'a> = &'a T;
+
+    fn init(
+        component_manager: &mut ComponentManager,
+        signature: &mut Signature,
+        access: &mut Access,
+    ) {
+        access.add_read::<T>();
+        init_component::<T>(component_manager, signature);
+    }
+
+    fn fetch(component_manager: &ComponentManager) -> Self::Fetch<'_> {
+        ColumnRef(
+            component_manager
+                .component_array(TypeId::of::<T>())
+                .read()
+                .unwrap(),
+        )
+    }
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: EntityType) -> Self::Item<'a> {
+        fetch.0.get_data::<T>(entity)
+    }
+
+    fn get_many<'a>(
+        fetch: &'a mut Self::Fetch<'_>,
+        entities: &[EntityType],
+    ) -> Vec<Self::Item<'a>> {
+        fetch.0.get_many::<T>(entities)
+    }
+}
+
+// SAFETY: `&T` read-locks its array and yields shared references.
+unsafe impl<T: IComponent> ReadOnlyQueryData for &T {}
+
+impl<T: IComponent> QueryData for &mut T {
+    type Fetch<'w> = ColumnMut<'w>;
+    type Item<'a> = &'a mut T;
+
+    fn init(
+        component_manager: &mut ComponentManager,
+        signature: &mut Signature,
+        access: &mut Access,
+    ) {
+        access.add_write::<T>();
+        init_component::<T>(component_manager, signature);
+    }
+
+    fn fetch(component_manager: &ComponentManager) -> Self::Fetch<'_> {
+        ColumnMut(
+            component_manager
+                .component_array(TypeId::of::<T>())
+                .write()
+                .unwrap(),
+        )
+    }
+
+    fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: EntityType) -> Self::Item<'a> {
+        fetch.0.get_data_mut::<T>(entity)
+    }
+
+    fn get_many<'a>(
+        fetch: &'a mut Self::Fetch<'_>,
+        entities: &[EntityType],
+    ) -> Vec<Self::Item<'a>> {
+        fetch.0.get_many_mut::<T>(entities)
+    }
+}
+
+macro_rules! impl_query_data {
+    ($($name:ident $fetch:ident),+) => {
+        impl<$($name: QueryData),+> QueryData for ($($name,)+) {
+            type Fetch<'w> = ($($name::Fetch<'w>,)+);
+            type Item<'a> = ($($name::Item<'a>,)+);
+
+            fn init(
+                component_manager: &mut ComponentManager,
+                signature: &mut Signature,
+                access: &mut Access,
+            ) {
+                $($name::init(component_manager, signature, access);)+
+            }
+
+            fn fetch(component_manager: &ComponentManager) -> Self::Fetch<'_> {
+                ($($name::fetch(component_manager),)+)
+            }
+
+            fn get<'a>(fetch: &'a mut Self::Fetch<'_>, entity: EntityType) -> Self::Item<'a> {
+                let ($($fetch,)+) = fetch;
+                ($($name::get($fetch, entity),)+)
+            }
+
+            fn get_many<'a>(
+                fetch: &'a mut Self::Fetch<'_>,
+                entities: &[EntityType],
+            ) -> Vec<Self::Item<'a>> {
+                let ($($fetch,)+) = fetch;
+                $(let mut $fetch = $name::get_many($fetch, entities).into_iter();)+
+                entities
+                    .iter()
+                    .map(|_| ($($fetch.next().unwrap(),)+))
+                    .collect()
+            }
+        }
+
+        // SAFETY: every element is read-only.
+        unsafe impl<$($name: ReadOnlyQueryData),+> ReadOnlyQueryData for ($($name,)+) {}
+    };
+}
+
+impl_query_data!(A a);
+impl_query_data!(A a, B b);
+impl_query_data!(A a, B b, C c);
+impl_query_data!(A a, B b, C c, D d);
+impl_query_data!(A a, B b, C c, D d, E e);
+impl_query_data!(A a, B b, C c, D d, E e, F f);
+impl_query_data!(A a, B b, C c, D d, E e, F f, G g);
+impl_query_data!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+/// Entities matching a signature, kept up to date by replaying the entity
+/// manager's change log instead of rescanning every entity.
+#[derive(Debug, Clone)]
+pub struct QueryCache {
+    signature: Signature,
+    matches: Vec<EntityType>,
+    cursor: Option<usize>,
+}
+
+impl QueryCache {
+    /// Creates an empty cache; the first `update` does the initial scan.
+    pub fn new(signature: Signature) -> Self {
+        Self {
+            signature,
+            matches: Vec::new(),
+            cursor: None,
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Follows a renumbering of component types. Matches are unaffected,
+    /// since no entity's components changed.
+    pub fn remap(&mut self, remap: &[ComponentType]) {
+        self.signature = remap_signature(&self.signature, remap);
+    }
+
+    /// Matched entities in id order, as of the last `update`.
+    pub fn matches(&self) -> &[EntityType] {
+        &self.matches
+    }
+
+    /// Brings the matches up to date, calling `on_change` with every entity
+    /// that started (`true`) or stopped (`false`) matching.
+    pub fn update<F: FnMut(EntityType, bool)>(
+        &mut self,
+        entity_manager: &EntityManager,
+        mut on_change: F,
+    ) {
+        let changes = entity_manager.changes();
+        let dirty = self.cursor.and_then(|cursor| changes.since(cursor));
+        self.cursor = Some(changes.end());
+        let Some(dirty) = dirty else {
+            return self.rebuild(entity_manager, on_change);
+        };
+        if dirty.is_empty() {
+            return;
+        }
+        if dirty.len() > self.matches.len().max(64) {
+            return self.rebuild(entity_manager, on_change);
+        }
+
+        let mut dirty = dirty.to_vec();
+        dirty.sort_unstable();
+        dirty.dedup();
+        for entity in dirty {
+            let matched = self.is_match(entity_manager, entity);
+            match (self.matches.binary_search(&entity), matched) {
+                (Ok(index), false) => {
+                    self.matches.remove(index);
+                    on_change(entity, false);
+                }
+                (Err(index), true) => {
+                    self.matches.insert(index, entity);
+                    on_change(entity, true);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn is_match(&self, entity_manager: &EntityManager, entity: EntityType) -> bool {
+        entity_manager
+            .signature(entity)
+            .is_some_and(|signature| self.signature.is_subset(signature))
+    }
+
+    fn rebuild<F: FnMut(EntityType, bool)>(
+        &mut self,
+        entity_manager: &EntityManager,
+        mut on_change: F,
+    ) {
+        let matches: Vec<EntityType> = entity_manager
+            .iter()
+            .filter(|(_, signature)| self.signature.is_subset(signature))
+            .map(|(entity, _)| entity)
+            .collect();
+
+        let (mut old, mut new) = (self.matches.iter().peekable(), matches.iter().peekable());
+        loop {
+            match (old.peek(), new.peek()) {
+                (Some(a), Some(b)) if a == b => {
+                    old.next();
+                    new.next();
+                }
+                (Some(a), Some(b)) if a < b => on_change(*old.next().unwrap(), false),
+                (Some(_), None) => on_change(*old.next().unwrap(), false),
+                (_, Some(_)) => on_change(*new.next().unwrap(), true),
+                (None, None) => break,
+            }
+        }
+        self.matches = matches;
+    }
+}
+
+/// Every entity that has all components of `Q`, with the component arrays
+/// locked for as long as the query lives.
+pub struct Query<'w, Q: QueryData> {
+    entities: &'w [EntityType],
+    fetch: Q::Fetch<'w>,
+    mode: ParallelMode,
+}
+
+/// How `Query::par_for_each` schedules its batches. Insert it as a resource
+/// to choose; `Parallel` is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParallelMode {
+    /// Batches run on rayon's work-stealing pool, in any order.
+    #[default]
+    Parallel,
+    /// Batches run one after another in entity order on the calling thread,
+    /// so replays observe exactly the same sequence.
+    Deterministic,
+}
+
+impl Resource for ParallelMode {}
+
+impl<Q: QueryData> Query<'_, Q> {
+    /// Matched entities in id order.
+    pub fn entities(&self) -> &[EntityType] {
+        self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn get(&mut self, entity: EntityType) -> Option<Q::Item<'_>> {
+        if self.entities.binary_search(&entity).is_err() {
+            return None;
+        }
+        Some(Q::get(&mut self.fetch, entity))
+    }
+
+    pub fn for_each<F: FnMut(Q::Item<'_>)>(&mut self, mut f: F) {
+        for entity in self.entities.iter() {
+            f(Q::get(&mut self.fetch, *entity));
+        }
+    }
+
+    /// Like `for_each`, running the matches in batches of `batch_size`
+    /// entities, each batch on one worker thread; the last batch may be
+    /// shorter. The items are gathered up front, one per matched entity.
+    /// Every item borrows its own components, and `f` is `Fn + Sync`, so
+    /// the borrow checker rules out two batches writing the same data.
+    pub fn par_for_each<F>(&mut self, batch_size: usize, f: F)
+    where
+        F: Fn(Q::Item<'_>) + Send + Sync,
+        for<'a> Q::Item<'a>: Send,
+    {
+        let items = Q::get_many(&mut self.fetch, self.entities);
+        match self.mode {
+            ParallelMode::Parallel => items
+                .into_par_iter()
+                .chunks(batch_size.max(1))
+                .for_each(|batch| batch.into_iter().for_each(&f)),
+            ParallelMode::Deterministic => items.into_iter().for_each(f),
+        }
+    }
+
+    pub fn for_each_with_entity<F: FnMut(EntityType, Q::Item<'_>)>(&mut self, mut f: F) {
+        for entity in self.entities.iter() {
+            f(*entity, Q::get(&mut self.fetch, *entity));
+        }
+    }
+}
+
+/// Cached matching for a `Query`, owned by a function system or created
+/// with `EcsSingleton::query` for ad-hoc use.
+pub struct QueryState<Q: QueryData> {
+    cache: QueryCache,
+    /// Whether the signature uses the ids of a frozen registry.
+    frozen_ids: bool,
+    marker: PhantomData<fn() -> Q>,
+}
+
+impl<Q: QueryData> QueryState<Q> {
+    pub fn new(singleton: &mut EcsSingleton) -> Self {
+        Self::with_access(singleton, &mut Access::default())
+    }
+
+    fn with_access(singleton: &mut EcsSingleton, access: &mut Access) -> Self {
+        let mut signature = Signature::default();
+        Q::init(singleton.component_manager_mut(), &mut signature, access);
+        Self {
+            cache: QueryCache::new(signature),
+            frozen_ids: singleton.component_manager().registry().is_frozen(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Catches up with structural changes and locks the component arrays.
+    pub fn query<'w>(&'w mut self, singleton: &'w EcsSingleton) -> Query<'w, Q> {
+        if !self.frozen_ids {
+            if let Some(remap) = singleton.component_manager().registry().freeze_map() {
+                self.cache.remap(remap);
+                self.frozen_ids = true;
+            }
+        }
+        self.cache.update(singleton.entity_manager(), |_, _| {});
+        Query {
+            entities: self.cache.matches(),
+            fetch: Q::fetch(singleton.component_manager()),
+            mode: singleton
+                .get_resource::<ParallelMode>()
+                .copied()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl<Q: QueryData + 'static> SystemParam for Query<'_, Q> {
+    type State = QueryState<Q>;
+    type Item<'w> = Query<'w, Q>;
+
+    fn init(singleton: &mut EcsSingleton, access: &mut Access) -> Self::State {
+        QueryState::with_access(singleton, access)
+    }
+
+    fn fetch<'w>(state: &'w mut Self::State, singleton: &'w EcsSingleton) -> Self::Item<'w> {
+        state.query(singleton)
+    }
+
+    fn matched(state: &Self::State) -> usize {
+        state.cache.matches().len()
+    }
+}
@@ -1,19 +1,160 @@
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    fmt, mem,
+    sync::{Mutex, PoisonError},
+};
 
-use crate::ecs::ecs::config::EntityType;
+use crate::ecs::ecs::{
+    config::{ComponentType, EntityType, Signature},
+    entity_manager::EntityManager,
+    singleton::EcsSingleton,
+};
+
+use super::{access::Access, condition::RunCondition, query::QueryCache};
+
+/// What the scheduler calls for a system, with the entities its signature
+/// matched. Returns how many entities the run covered, for diagnostics.
+pub type SystemRun = Box<dyn FnMut(&mut EcsSingleton, &[EntityType]) -> usize + Send>;
 
-#[derive(Debug)]
 pub struct System {
-    pub entities: HashSet<EntityType>,
+    pub(crate) name: &'static str,
+    pub(crate) access: Access,
+    pub(crate) entities: HashSet<EntityType>,
+    pub(crate) enabled: bool,
+    pub(crate) run_conditions: Vec<RunCondition>,
+    pub(crate) run: Option<SystemRun>,
+    query: Option<QueryCache>,
 }
 impl System {
-    pub fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
+            name: "",
+            access: Access::exclusive(),
             entities: HashSet::new(),
+            enabled: true,
+            run_conditions: Vec::new(),
+            run: None,
+            query: None,
+        }
+    }
+
+    /// Type name of the system marker or function.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Entities matched by the signature. The world updates the set after
+    /// every structural change, so it is current even for a system handle
+    /// kept from an earlier `get_system`.
+    pub fn entities(&self) -> &HashSet<EntityType> {
+        &self.entities
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Components the system reads and writes.
+    pub fn access(&self) -> &Access {
+        &self.access
+    }
+
+    /// Matches entities against `signature` from now on, starting over.
+    pub fn set_signature(&mut self, signature: Signature) {
+        self.entities.clear();
+        self.query = Some(QueryCache::new(signature));
+    }
+
+    /// The signature entities are matched against, if one was set.
+    pub fn signature(&self) -> Option<&Signature> {
+        self.query.as_ref().map(|query| query.signature())
+    }
+
+    pub(crate) fn remap_signature(&mut self, remap: &[ComponentType]) {
+        if let Some(query) = self.query.as_mut() {
+            query.remap(remap);
         }
     }
+
+    /// Brings `entities` up to date with the structural changes made since
+    /// the last refresh. Systems without a signature match nothing.
+    pub(crate) fn refresh(&mut self, entity_manager: &EntityManager) {
+        let Some(query) = self.query.as_mut() else {
+            return;
+        };
+        let entities = &mut self.entities;
+        query.update(entity_manager, |entity, matched| {
+            if matched {
+                entities.insert(entity);
+            } else {
+                entities.remove(&entity);
+            }
+        });
+    }
     pub fn run_system(&self, f: fn(&System)) {
         f(self);
     }
 }
-pub struct Physics {}
+
+/// The run closure and conditions of a system, taken out for one scheduled
+/// run so the system can be locked, and changed, while it runs. They go
+/// back on drop, even if the run panics: a closure set with
+/// `set_system_run` during the run is kept instead, and conditions added
+/// with `run_if` are kept after the taken ones.
+pub(crate) struct TakenRun<'a> {
+    system: &'a Mutex<System>,
+    pub run: Option<SystemRun>,
+    pub conditions: Vec<RunCondition>,
+}
+
+impl<'a> TakenRun<'a> {
+    /// `None` if the system is disabled or has nothing to run.
+    pub fn take(system: &'a Mutex<System>) -> Option<Self> {
+        let mut guard = system.lock().unwrap();
+        if !guard.enabled {
+            return None;
+        }
+        let run = Some(guard.run.take()?);
+        let conditions = mem::take(&mut guard.run_conditions);
+        drop(guard);
+        Some(Self {
+            system,
+            run,
+            conditions,
+        })
+    }
+}
+
+impl Drop for TakenRun<'_> {
+    fn drop(&mut self) {
+        let mut guard = self.system.lock().unwrap_or_else(PoisonError::into_inner);
+        if guard.run.is_none() {
+            guard.run = self.run.take();
+        }
+        let added = mem::replace(&mut guard.run_conditions, mem::take(&mut self.conditions));
+        guard.run_conditions.extend(added);
+    }
+}
+
+/// A snapshot of one scheduled system, for tools that list them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemInfo {
+    pub name: &'static str,
+    pub enabled: bool,
+    /// Entities matched by the system's signature; function systems find
+    /// theirs through queries and report zero.
+    pub entities: usize,
+}
+
+impl fmt::Debug for System {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("System")
+            .field("name", &self.name)
+            .field("access", &self.access)
+            .field("entities", &self.entities)
+            .field("enabled", &self.enabled)
+            .field("run_conditions", &self.run_conditions.len())
+            .field("run", &self.run.is_some())
+            .finish()
+    }
+}
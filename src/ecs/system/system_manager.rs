@@ -1,24 +1,27 @@
 use std::{
-    any::{Any, TypeId},
+    any::{type_name, Any, TypeId},
     collections::HashMap,
     sync::{Arc, Mutex},
 };
 
-use crate::ecs::ecs::config::{EntityType, Signature};
+use crate::ecs::ecs::config::Signature;
 
-use super::system::System;
+use super::{
+    access::{Access, SystemConflict},
+    system::{System, SystemRun},
+};
 
 #[derive(Debug)]
 pub struct SystemManager {
-    signatures: HashMap<TypeId, Signature>,
     systems: HashMap<TypeId, Arc<Mutex<System>>>,
+    order: Vec<TypeId>,
 }
 
 impl SystemManager {
     pub fn new() -> Self {
         Self {
-            signatures: HashMap::new(),
             systems: HashMap::new(),
+            order: Vec::new(),
         }
     }
 
@@ -31,52 +34,92 @@ impl SystemManager {
         }
     }
     pub fn register_system<T: Any>(&mut self) -> TypeId {
-        let system_shared_value = Mutex::new(System::new());
-        let arc_system = Arc::new(system_shared_value);
-        let type_id = TypeId::of::<T>();
-
-        if let None = self.systems.insert(type_id, arc_system) {
-            return type_id;
-        }
-        panic!("Trying to add a duplicate system")
+        let mut system = System::new();
+        system.name = type_name::<T>();
+        self.insert_system(TypeId::of::<T>(), system)
     }
 
-    pub fn register_system_func<T: Any>(&mut self) -> TypeId {
-        let system_shared_value = Mutex::new(System::new());
-        let arc_system = Arc::new(system_shared_value);
-        let type_id = TypeId::of::<T>();
+    /// Registers a function system under the type of `F`, with the access
+    /// declared by its parameters. It matches no entities itself; its
+    /// queries find them when it runs.
+    pub fn register_system_func<F: Any>(&mut self, access: Access, run: SystemRun) -> TypeId {
+        let mut system = System::new();
+        system.name = type_name::<F>();
+        system.access = access;
+        system.run = Some(run);
+        self.insert_system(TypeId::of::<F>(), system)
+    }
 
-        if let None = self.systems.insert(type_id, arc_system) {
+    fn insert_system(&mut self, type_id: TypeId, system: System) -> TypeId {
+        if self
+            .systems
+            .insert(type_id, Arc::new(Mutex::new(system)))
+            .is_none()
+        {
+            self.order.push(type_id);
             return type_id;
         }
         panic!("Trying to add a duplicate system")
     }
 
-    pub fn set_signatures<T: Any>(&mut self, signature: Signature) {
-        self.signatures.insert(TypeId::of::<T>(), signature);
+    /// Removes the system together with its signature and entity set.
+    pub fn unregister_system<T: Any>(&mut self) {
+        self.unregister_system_by_id(TypeId::of::<T>());
     }
 
-    pub fn entity_destroyed(&mut self, entity: EntityType) {
-        for system in self.systems.iter_mut() {
-            let (_, system_shared_ptr) = system;
-            let system_shared = system_shared_ptr.clone();
-            if let Ok(mut system_mutext) = system_shared.lock() {
-                system_mutext.entities.remove(&entity);
-            };
-        }
+    /// Like `unregister_system`, for the id returned at registration, e.g.
+    /// of a function system.
+    pub fn unregister_system_by_id(&mut self, type_id: TypeId) {
+        assert!(
+            self.systems.remove(&type_id).is_some(),
+            "Cant retriver given system"
+        );
+        self.order.retain(|id| *id != type_id);
     }
-    pub fn entity_signature_changed(&mut self, entity: EntityType, signature: &Signature) {
-        for system in self.systems.iter_mut() {
-            let (type_id, system_shared_ptr) = system;
-            if let Ok(mut system_mutext) = system_shared_ptr.clone().lock() {
-                if let Some(self_signature) = self.signatures.get(&type_id) {
-                    if self_signature.is_subset(signature) {
-                        system_mutext.entities.insert(entity);
-                    } else {
-                        system_mutext.entities.remove(&entity);
-                    }
+
+    /// Every pair of systems, in scheduling order, whose declared access
+    /// overlaps with at least one write. Systems without a declared access
+    /// conflict with everything.
+    pub fn conflicts(&self) -> Vec<SystemConflict> {
+        let systems: Vec<_> = self
+            .order
+            .iter()
+            .map(|type_id| self.systems[type_id].lock().unwrap())
+            .collect();
+        let mut conflicts = Vec::new();
+        for (i, first) in systems.iter().enumerate() {
+            for second in systems[i + 1..].iter() {
+                let on = first.access.conflicts(&second.access);
+                if !on.is_empty() {
+                    conflicts.push(SystemConflict {
+                        first: first.name,
+                        second: second.name,
+                        on,
+                    });
                 }
             }
         }
+        conflicts
+    }
+
+    /// Systems in registration order, which is the order the scheduler runs
+    /// them in.
+    pub fn scheduled(&self) -> Vec<(TypeId, Arc<Mutex<System>>)> {
+        self.order
+            .iter()
+            .map(|type_id| (*type_id, Arc::clone(&self.systems[type_id])))
+            .collect()
+    }
+
+    /// Every system, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Mutex<System>>> {
+        self.systems.values()
+    }
+
+    pub fn set_signatures<T: Any>(&mut self, signature: Signature) {
+        self.get_system::<T>()
+            .lock()
+            .unwrap()
+            .set_signature(signature);
     }
 }
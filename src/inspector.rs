@@ -0,0 +1,322 @@
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+use singleton_ecs::{
+    config::EntityType,
+    reflect::{ReflectError, ReflectValue},
+    scene::Scene,
+    singleton::EcsSingleton,
+};
+
+use crate::runner::{self, RunnerConfig, RunnerError};
+
+pub const HELP: &str = "\
+entities                          list live entities and their components
+components <id>                   list the components of an entity
+get <id> <Component[.path]>       print a component or one of its fields
+set <id> <Component.path> <value> write a field
+despawn <id>                      destroy an entity
+systems                           list systems in scheduling order
+step [n]                          advance the world n ticks (default 1)
+help                              show this text
+quit                              leave the inspector";
+
+#[derive(Debug, PartialEq)]
+pub enum InspectorError {
+    UnknownCommand(String),
+    Usage(&'static str),
+    InvalidEntity(String),
+    NotAlive(EntityType),
+    Reflect(ReflectError),
+}
+
+impl fmt::Display for InspectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InspectorError::UnknownCommand(command) => {
+                write!(f, "unknown command {}, try help", command)
+            }
+            InspectorError::Usage(usage) => write!(f, "usage: {}", usage),
+            InspectorError::InvalidEntity(id) => write!(f, "{} is not an entity id", id),
+            InspectorError::NotAlive(entity) => write!(f, "entity {} is not alive", entity),
+            InspectorError::Reflect(error) => write!(f, "{:?}", error),
+        }
+    }
+}
+
+impl From<ReflectError> for InspectorError {
+    fn from(error: ReflectError) -> Self {
+        InspectorError::Reflect(error)
+    }
+}
+
+/// Runs inspector commands against a live world. `rate` is the tick rate
+/// used by `step`.
+pub struct Inspector<'w> {
+    singleton: &'w mut EcsSingleton,
+    rate: f32,
+}
+
+impl<'w> Inspector<'w> {
+    pub fn new(singleton: &'w mut EcsSingleton, rate: f32) -> Self {
+        Self { singleton, rate }
+    }
+
+    /// Runs one command line and returns what it prints.
+    pub fn execute(&mut self, line: &str) -> Result<String, InspectorError> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(String::new()),
+            ["help"] => Ok(HELP.to_string()),
+            ["entities"] => Ok(self.entities()),
+            ["components", id] => self.components(self.entity(id)?),
+            ["components", ..] => Err(InspectorError::Usage("components <id>")),
+            ["get", id, path] => self.get(self.entity(id)?, path),
+            ["get", ..] => Err(InspectorError::Usage("get <id> <Component[.path]>")),
+            ["set", id, path, value] => self.set(self.entity(id)?, path, value),
+            ["set", ..] => Err(InspectorError::Usage("set <id> <Component.path> <value>")),
+            ["despawn", id] => {
+                let entity = self.entity(id)?;
+                self.singleton.destroy_entity(entity);
+                Ok(format!("despawned {}", entity))
+            }
+            ["despawn", ..] => Err(InspectorError::Usage("despawn <id>")),
+            ["systems"] => Ok(self.systems()),
+            ["step"] => self.step(1),
+            ["step", n] => self.step(n.parse().map_err(|_| InspectorError::Usage("step [n]"))?),
+            [command, ..] => Err(InspectorError::UnknownCommand(command.to_string())),
+        }
+    }
+
+    fn entity(&self, id: &str) -> Result<EntityType, InspectorError> {
+        let entity = id
+            .parse()
+            .map_err(|_| InspectorError::InvalidEntity(id.to_string()))?;
+        if !self.singleton.is_alive(entity) {
+            return Err(InspectorError::NotAlive(entity));
+        }
+        Ok(entity)
+    }
+
+    fn entities(&self) -> String {
+        let lines: Vec<String> = self
+            .singleton
+            .entities()
+            .map(|(entity, _)| {
+                let names: Vec<String> = self
+                    .singleton
+                    .entity_components(entity)
+                    .into_iter()
+                    .map(|info| info.name)
+                    .collect();
+                format!("{}: {}", entity, names.join(", "))
+            })
+            .collect();
+        format!(
+            "{}\n{} entities",
+            lines.join("\n"),
+            self.singleton.entity_count()
+        )
+        .trim_start()
+        .to_string()
+    }
+
+    fn components(&self, entity: EntityType) -> Result<String, InspectorError> {
+        let lines: Vec<String> = self
+            .singleton
+            .entity_components(entity)
+            .into_iter()
+            .map(|info| {
+                let kind = if info.tag {
+                    "tag"
+                } else if info.is_dynamic() {
+                    "dynamic"
+                } else if info.reflect.is_some() {
+                    "reflect"
+                } else {
+                    "opaque"
+                };
+                format!("{} [{}] {}", info.name, kind, info.type_name)
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    fn get(&self, entity: EntityType, path: &str) -> Result<String, InspectorError> {
+        let (component, field) = split_path(path);
+        if field.is_empty() {
+            return Ok(self.singleton.debug_component(entity, component)?);
+        }
+        Ok(self
+            .singleton
+            .get_component_field(entity, component, field)?
+            .to_string())
+    }
+
+    fn set(
+        &mut self,
+        entity: EntityType,
+        path: &str,
+        value: &str,
+    ) -> Result<String, InspectorError> {
+        let (component, field) = split_path(path);
+        if field.is_empty() {
+            return Err(InspectorError::Usage("set <id> <Component.path> <value>"));
+        }
+        let value = ReflectValue::parse(value);
+        self.singleton
+            .set_component_field(entity, component, field, value)?;
+        self.get(entity, path)
+    }
+
+    fn systems(&self) -> String {
+        let lines: Vec<String> = self
+            .singleton
+            .system_infos()
+            .into_iter()
+            .map(|info| {
+                let state = if info.enabled { "enabled" } else { "disabled" };
+                format!("{} [{}] {} entities", info.name, state, info.entities)
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    fn step(&mut self, ticks: u64) -> Result<String, InspectorError> {
+        let mut last = None;
+        runner::step(self.singleton, ticks, self.rate, false, |stats| {
+            last = Some(stats.clone())
+        });
+        Ok(last.map_or(String::new(), |stats| stats.to_json().to_string()))
+    }
+}
+
+/// `Transform.position.x` into `("Transform", "position.x")`.
+fn split_path(path: &str) -> (&str, &str) {
+    path.split_once('.').unwrap_or((path, ""))
+}
+
+/// Reads commands from `input` until `quit` or end of input, writing results
+/// and errors to `output`.
+pub fn repl<R: BufRead, W: Write>(
+    inspector: &mut Inspector,
+    input: R,
+    mut output: W,
+) -> io::Result<()> {
+    write!(output, "> ")?;
+    output.flush()?;
+    for line in input.lines() {
+        let line = line?;
+        if matches!(line.trim(), "quit" | "exit") {
+            break;
+        }
+        match inspector.execute(&line) {
+            Ok(text) if text.is_empty() => {}
+            Ok(text) => writeln!(output, "{}", text)?,
+            Err(error) => writeln!(output, "error: {}", error)?,
+        }
+        write!(output, "> ")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Loads the scene of `config` and inspects it from the terminal.
+pub fn run(config: &RunnerConfig) -> Result<(), RunnerError> {
+    let scene = Scene::load(&config.scene).map_err(RunnerError::Scene)?;
+    let mut singleton = runner::load_world(&scene).map_err(RunnerError::Scene)?;
+    let mut inspector = Inspector::new(&mut singleton, config.rate);
+    repl(&mut inspector, io::stdin().lock(), io::stdout().lock())
+        .map_err(|e| RunnerError::Io(e.to_string()))
+}
+
+#[cfg(test)]
+mod inspector {
+    use super::*;
+
+    const SCENE: &str = r#"{
+        "entities": [
+            {
+                "components": {
+                    "Transform": { "position": [1.0, 2.0, 3.0] },
+                    "RigidBody": { "velocity": [1.0, 0.0, 0.0] }
+                }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_inspect_and_edit_entity() {
+        let mut singleton = runner::load_world(&Scene::from_json(SCENE).unwrap()).unwrap();
+        let mut inspector = Inspector::new(&mut singleton, 1.0);
+
+        assert_eq!(
+            inspector.execute("entities").unwrap(),
+            "0: Transform, RigidBody\n1 entities"
+        );
+        assert!(inspector
+            .execute("components 0")
+            .unwrap()
+            .starts_with("Transform [reflect]"));
+        assert_eq!(
+            inspector.execute("get 0 Transform.position.y").unwrap(),
+            "2"
+        );
+        assert_eq!(
+            inspector.execute("set 0 Transform.position.x 3").unwrap(),
+            "3"
+        );
+        assert_eq!(
+            inspector.execute("get 0 Transform").unwrap(),
+            "Transform { position: Vec3 { x: 3, y: 2, z: 3 } }"
+        );
+        assert!(inspector
+            .execute("systems")
+            .unwrap()
+            .contains("integrate [enabled]"));
+
+        inspector.execute("step 2").unwrap();
+        assert_eq!(
+            inspector.execute("get 0 Transform.position.x").unwrap(),
+            "5"
+        );
+
+        assert_eq!(
+            inspector.execute("get 0 Mana"),
+            Err(InspectorError::Reflect(ReflectError::UnknownComponent(
+                "Mana".to_string()
+            )))
+        );
+        assert_eq!(inspector.execute("despawn 0").unwrap(), "despawned 0");
+        assert_eq!(
+            inspector.execute("components 0"),
+            Err(InspectorError::NotAlive(0))
+        );
+        assert!(matches!(
+            inspector.execute("fly 0"),
+            Err(InspectorError::UnknownCommand(_))
+        ));
+    }
+
+    #[test]
+    fn test_repl_stops_on_quit() {
+        let mut singleton = runner::load_world(&Scene::default()).unwrap();
+        let mut inspector = Inspector::new(&mut singleton, 60.0);
+        let mut output = Vec::new();
+
+        repl(
+            &mut inspector,
+            "entities\nbogus\nquit\nentities\n".as_bytes(),
+            &mut output,
+        )
+        .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "> 0 entities\n> error: unknown command bogus, try help\n> "
+        );
+    }
+}
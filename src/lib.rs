@@ -0,0 +1,60 @@
+//! An entity component system built around one `EcsSingleton` world.
+//!
+//! Most programs only need the `prelude`; the modules below expose the rest
+//! of the public API. Storage, entity bookkeeping and scheduling internals
+//! stay private.
+#![allow(clippy::module_inception)]
+
+// Lets the derive macros name this crate as `::singleton_ecs` from inside it
+// too.
+extern crate self as singleton_ecs;
+
+mod ecs;
+
+/// Differential test harness for fuzzers, behind the `model` feature.
+#[cfg(feature = "model")]
+pub use ecs::ecs::model;
+pub use ecs::ecs::{
+    access, builtin, bundle, clone, condition, config, diagnostics, event, hierarchy, param, query,
+    reflect, registry, relation, scene, shared, singleton, state, system,
+};
+pub use singleton_ecs_macros::{Bundle, Component, Resource};
+
+pub mod component {
+    pub use crate::ecs::ecs::component::{BoxedComponent, Component, IComponent, StorageProblem};
+}
+
+pub mod dynamic {
+    pub use crate::ecs::ecs::dynamic::DynamicDropFn;
+}
+
+pub mod resource {
+    pub use crate::ecs::ecs::resource_manager::Resource;
+}
+
+pub mod validate {
+    pub use crate::ecs::ecs::validate::InvariantViolation;
+}
+
+/// The types and functions most systems and setup code use.
+pub mod prelude {
+    pub use crate::{
+        bundle::Bundle,
+        clone::ClonePolicy,
+        component::{Component, IComponent},
+        condition::{not, resource_equals, resource_exists},
+        config::{ComponentType, EntityType, Signature},
+        event::{EventReader, EventWriter},
+        hierarchy::{Children, Parent},
+        param::{Commands, Res, ResMut},
+        query::{ParallelMode, Query, QueryState, ReadOnlyQueryData},
+        reflect::Reflect,
+        relation::{Relation, RelationArity, RelationCleanup},
+        resource::Resource,
+        scene::Scene,
+        shared::SharedWorld,
+        singleton::EcsSingleton,
+        state::{in_state, OnEnter, OnExit, OnTransition, State, StateScoped, States},
+        Bundle, Component, Resource,
+    };
+}
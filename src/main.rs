@@ -1,124 +1,38 @@
-mod ecs;
-fn main() {}
-#[cfg(test)]
+#![allow(clippy::module_inception)]
 
-mod test {
-    use std::sync::{Arc, Mutex};
+mod bench;
+mod inspector;
+mod runner;
 
-    use ecs::ecs::{
-        component::IComponent, config::Signature, singleton::EcsSingleton, system::System,
-    };
-    use glam::Vec3;
-
-    use super::*;
+use std::process::ExitCode;
 
-    #[derive(Debug)]
-    struct Transform {
-        position: Vec3,
-    }
+use bench::BenchConfig;
+use runner::{RunnerConfig, RunnerError};
 
-    impl IComponent for Transform {
-        fn entity_destroyed(&mut self, _entity: ecs::ecs::config::EntityType) {
-            todo!()
-        }
-    }
-
-    #[derive(Debug)]
-    struct RigidBody {
-        force: Vec3,
-    }
-    struct Physics<'a, 'c> {
-        singleton: &'c EcsSingleton,
-        system: &'a Arc<Mutex<System>>,
+fn main() -> ExitCode {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        Some(command @ ("inspect" | "bench")) => command.to_string(),
+        _ => String::new(),
+    };
+    if !command.is_empty() {
+        args.remove(0);
     }
 
-    impl<'a, 'c> Physics<'a, 'c> {
-        pub fn new(singleton: &'c EcsSingleton, system: &'a Arc<Mutex<System>>) -> Self {
-            Physics { singleton, system }
-        }
-
-        pub fn process(&mut self) {
-            for sys in self.system.lock().iter() {
-                for entity in sys.entities.iter() {
-                    self.singleton
-                        .get_component_mut::<Transform, _, ()>(*entity, |comp| {
-                            comp.position.x += 1.0;
-                        });
-
-                    self.singleton
-                        .get_component_mut::<RigidBody, _, ()>(*entity, |comp| {
-                            comp.force.x += 1.0;
-                        });
-                }
-            }
+    let result = match command.as_str() {
+        "bench" => BenchConfig::from_args(args).and_then(|config| bench::run(&config)),
+        "inspect" => RunnerConfig::from_args(args).and_then(|config| inspector::run(&config)),
+        _ => RunnerConfig::from_args(args).and_then(|config| runner::run(&config)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error @ (RunnerError::Usage(_) | RunnerError::BenchUsage(_))) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
         }
-    }
-
-    impl IComponent for RigidBody {
-        fn entity_destroyed(&mut self, _entity: ecs::ecs::config::EntityType) {
-            todo!()
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
         }
     }
-
-    #[test]
-    pub fn test_register_entity_to_singleton() {
-        let mut singleton = EcsSingleton::new();
-        // created my components
-        singleton.register_component::<Transform>();
-        singleton.register_component::<RigidBody>();
-
-        let entity = singleton.create_entity();
-
-        let force = Vec3::new(1.0, 1.0, 1.0);
-        singleton.add_component(entity, RigidBody { force });
-        let position = Vec3::new(1.0, 0.0, 1.0);
-        singleton.add_component(entity, Transform { position });
-        let rigid_body = singleton.get_component::<RigidBody>(entity);
-
-        let transform_component = singleton.get_component::<Transform>(entity);
-
-        assert_eq!(transform_component.position, position);
-
-        assert_eq!(rigid_body.force, force);
-    }
-
-    #[test]
-    pub fn test_register_system() {
-        let mut singleton = EcsSingleton::new();
-        // created my components
-        singleton.register_component::<Transform>();
-        singleton.register_component::<RigidBody>();
-
-        singleton.register_system::<Physics>();
-
-        let mut signature: Signature = Default::default();
-        signature.insert(singleton.get_component_type::<Transform>() as usize);
-        signature.insert(singleton.get_component_type::<RigidBody>() as usize);
-        singleton.set_system_signature::<Physics>(signature);
-
-        let entity = singleton.create_entity();
-
-        let force = Vec3::new(1.0, 1.0, 1.0);
-        singleton.add_component(entity, RigidBody { force });
-        let position = Vec3::new(1.0, 0.0, 1.0);
-        singleton.add_component(entity, Transform { position });
-
-        let transform_component = singleton.get_component::<Transform>(entity);
-        let rigid_body = singleton.get_component::<RigidBody>(entity);
-
-        assert_eq!(transform_component.position, position);
-
-        assert_eq!(rigid_body.force, force);
-
-        let gh = singleton.get_system::<Physics>().clone();
-        let mut physics = Physics::new(&singleton, &gh);
-        physics.process();
-
-        let transform_component = singleton.get_component::<Transform>(entity);
-        let rigid_body = singleton.get_component::<RigidBody>(entity);
-
-        assert_eq!(transform_component.position, Vec3::new(2.0, 0.0, 1.0));
-
-        assert_eq!(rigid_body.force, Vec3::new(2.0, 1.0, 1.0));
-    }
 }
@@ -0,0 +1,306 @@
+use std::{
+    fmt, fs,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use singleton_ecs::{
+    builtin::{register_builtins, Time},
+    diagnostics::{ChromeTraceWriter, Diagnostics},
+    scene::{Scene, SceneError},
+    singleton::EcsSingleton,
+};
+
+use crate::bench;
+
+/// `inspect` opens the inspector on the loaded scene instead of running it;
+/// only `--rate` applies to it.
+pub const USAGE: &str = "usage: singleton_ecs [inspect] <scene.(json|ron)> \
+    [--ticks N] [--rate HZ] [--realtime] [--dump PATH] [--trace PATH]";
+
+/// Options for one headless run, read from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunnerConfig {
+    pub scene: PathBuf,
+    pub ticks: u64,
+    /// Fixed simulation rate; every tick advances `Time` by `1 / rate`.
+    pub rate: f32,
+    /// Sleep between ticks so the run takes as long as it would in game.
+    pub realtime: bool,
+    /// Where to write the final world dump, pretty-printed. When `None` it
+    /// goes to stdout as one more JSON line after the tick stats.
+    pub dump: Option<PathBuf>,
+    /// Where to write a Chrome trace of every tick, if anywhere.
+    pub trace: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum RunnerError {
+    Usage(String),
+    /// A `bench` argument error, shown with the bench usage.
+    BenchUsage(String),
+    Scene(SceneError),
+    Io(String),
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunnerError::Usage(message) => write!(f, "{}\n{}", message, USAGE),
+            RunnerError::BenchUsage(message) => write!(f, "{}\n{}", message, bench::USAGE),
+            RunnerError::Scene(error) => write!(f, "scene error: {:?}", error),
+            RunnerError::Io(message) => write!(f, "io error: {}", message),
+        }
+    }
+}
+
+impl RunnerConfig {
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, RunnerError> {
+        let mut scene = None;
+        let mut config = RunnerConfig {
+            scene: PathBuf::new(),
+            ticks: 60,
+            rate: 60.0,
+            realtime: false,
+            dump: None,
+            trace: None,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ticks" => config.ticks = parse_value(&arg, args.next())?,
+                "--rate" => config.rate = parse_value(&arg, args.next())?,
+                "--dump" => config.dump = Some(parse_value(&arg, args.next())?),
+                "--trace" => config.trace = Some(parse_value(&arg, args.next())?),
+                "--realtime" => config.realtime = true,
+                flag if flag.starts_with("--") => {
+                    return Err(RunnerError::Usage(format!("unknown option {}", flag)))
+                }
+                _ if scene.is_none() => scene = Some(PathBuf::from(arg)),
+                _ => return Err(RunnerError::Usage(format!("unexpected argument {}", arg))),
+            }
+        }
+
+        // An infinite rate would step with a zero delta, and a tiny one with
+        // an infinite delta.
+        let delta = 1.0 / config.rate;
+        if !(config.rate.is_finite() && config.rate > 0.0 && delta.is_finite()) {
+            return Err(RunnerError::Usage(
+                "--rate must be positive and finite".to_string(),
+            ));
+        }
+        config.scene = scene.ok_or_else(|| RunnerError::Usage("missing scene".to_string()))?;
+        match config.scene.extension().and_then(|e| e.to_str()) {
+            Some("json" | "ron") => Ok(config),
+            _ => Err(RunnerError::Usage(format!(
+                "scene {} must be a .json or .ron file",
+                config.scene.display()
+            ))),
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, RunnerError> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| RunnerError::Usage(format!("{} needs a valid value", flag)))
+}
+
+/// What one tick did, printed as a JSON line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickStats {
+    pub tick: u64,
+    pub entities: usize,
+    pub duration: Duration,
+}
+
+impl TickStats {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "tick": self.tick,
+            "entities": self.entities,
+            "micros": self.duration.as_micros() as u64,
+        })
+    }
+}
+
+/// A world with the built-in components and systems and one scene spawned
+/// into it.
+pub fn load_world(scene: &Scene) -> Result<EcsSingleton, SceneError> {
+    let mut singleton = EcsSingleton::new();
+    register_builtins(&mut singleton);
+    singleton.instantiate(scene)?;
+    Ok(singleton)
+}
+
+/// Steps `singleton` `ticks` times at `rate` Hz, calling `on_tick` after
+/// each one.
+pub fn step<F: FnMut(&TickStats)>(
+    singleton: &mut EcsSingleton,
+    ticks: u64,
+    rate: f32,
+    realtime: bool,
+    mut on_tick: F,
+) {
+    let delta = 1.0 / rate;
+    for _ in 0..ticks {
+        let start = Instant::now();
+        singleton
+            .get_resource_mut::<Time>()
+            .expect("Time resource missing")
+            .advance(delta);
+        singleton.update();
+        let duration = start.elapsed();
+
+        on_tick(&TickStats {
+            tick: singleton.get_resource::<Time>().unwrap().tick,
+            entities: singleton.entity_count(),
+            duration,
+        });
+        if realtime {
+            thread::sleep(Duration::from_secs_f32(delta).saturating_sub(duration));
+        }
+    }
+}
+
+/// Every live entity with its components. Components without reflection
+/// data are listed as `null`.
+pub fn dump_world(singleton: &EcsSingleton) -> Value {
+    let entities: Vec<Value> = singleton
+        .entities()
+        .map(|(entity, _)| {
+            let components: serde_json::Map<String, Value> = singleton
+                .entity_components(entity)
+                .into_iter()
+                .map(|info| {
+                    let value = singleton
+                        .component_to_json(entity, &info.name)
+                        .unwrap_or(Value::Null);
+                    (info.name, value)
+                })
+                .collect();
+            json!({ "id": entity, "components": components })
+        })
+        .collect();
+    let tick = singleton.get_resource::<Time>().map_or(0, |time| time.tick);
+    json!({ "tick": tick, "entities": entities })
+}
+
+pub fn run(config: &RunnerConfig) -> Result<(), RunnerError> {
+    let scene = Scene::load(&config.scene).map_err(RunnerError::Scene)?;
+    let mut singleton = load_world(&scene).map_err(RunnerError::Scene)?;
+    // Frames are streamed to the trace as they finish, so diagnostics only
+    // need to keep the last one.
+    let mut trace = match &config.trace {
+        Some(path) => {
+            singleton.enable_diagnostics(1);
+            let file = fs::File::create(path).map_err(|e| RunnerError::Io(e.to_string()))?;
+            Some(
+                ChromeTraceWriter::new(BufWriter::new(file))
+                    .map_err(|e| RunnerError::Io(e.to_string()))?,
+            )
+        }
+        None => None,
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for _ in 0..config.ticks {
+        let mut result = Ok(());
+        step(&mut singleton, 1, config.rate, config.realtime, |stats| {
+            result = writeln!(out, "{}", stats.to_json());
+        });
+        if let Some(trace) = trace.as_mut() {
+            let diagnostics = singleton.get_resource::<Diagnostics>().unwrap();
+            result = result.and(trace.write_frame(diagnostics.last_frame().unwrap()));
+        }
+        result.map_err(|e| RunnerError::Io(e.to_string()))?;
+    }
+    if let Some(trace) = trace {
+        trace.finish().map_err(|e| RunnerError::Io(e.to_string()))?;
+    }
+
+    let dump = dump_world(&singleton);
+    match &config.dump {
+        Some(path) => fs::write(path, serde_json::to_string_pretty(&dump).unwrap()),
+        None => writeln!(out, "{}", dump),
+    }
+    .map_err(|e| RunnerError::Io(e.to_string()))
+}
+
+#[cfg(test)]
+mod runner {
+    use super::*;
+
+    const SCENE: &str = r#"{
+        "entities": [
+            {
+                "name": "ball",
+                "components": {
+                    "Transform": { "position": [0.0, 10.0, 0.0] },
+                    "RigidBody": { "velocity": [2.0, 0.0, 0.0] }
+                }
+            },
+            { "components": { "Transform": { "position": [1.0, 1.0, 1.0] } } }
+        ]
+    }"#;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let config =
+            RunnerConfig::from_args(args(&["level.ron", "--ticks", "5", "--rate", "30"])).unwrap();
+        assert_eq!(config.scene, PathBuf::from("level.ron"));
+        assert_eq!(config.ticks, 5);
+        assert_eq!(config.rate, 30.0);
+        assert!(!config.realtime);
+        assert!(config.trace.is_none());
+
+        assert!(matches!(
+            RunnerConfig::from_args(args(&["--ticks", "x", "level.ron"])),
+            Err(RunnerError::Usage(_))
+        ));
+        assert!(matches!(
+            RunnerConfig::from_args(args(&[])),
+            Err(RunnerError::Usage(_))
+        ));
+        assert!(matches!(
+            RunnerConfig::from_args(args(&["level.txt"])),
+            Err(RunnerError::Usage(_))
+        ));
+        for rate in ["0", "-1", "inf", "NaN", "1e-40"] {
+            assert!(matches!(
+                RunnerConfig::from_args(args(&["level.json", "--rate", rate])),
+                Err(RunnerError::Usage(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_step_and_dump() {
+        let mut singleton = load_world(&Scene::from_json(SCENE).unwrap()).unwrap();
+
+        let mut stats = Vec::new();
+        step(&mut singleton, 4, 2.0, false, |tick| {
+            stats.push(tick.clone())
+        });
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats[3].tick, 4);
+        assert_eq!(stats[3].entities, 2);
+
+        let dump = dump_world(&singleton);
+        assert_eq!(dump["tick"], 4);
+        let ball = &dump["entities"][0]["components"];
+        assert_eq!(ball["Transform"]["position"]["x"], 4.0);
+        assert_eq!(ball["RigidBody"]["mass"], 1.0);
+        assert!(dump["entities"][1]["components"]["RigidBody"].is_null());
+    }
+}
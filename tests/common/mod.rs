@@ -0,0 +1,43 @@
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use glam::Vec3;
+use singleton_ecs::{prelude::*, system::System};
+
+#[derive(Debug, Clone, Component, Resource)]
+pub struct Transform {
+    pub position: Vec3,
+}
+
+#[derive(Debug, Component)]
+pub struct RigidBody {
+    pub force: Vec3,
+}
+
+pub struct Physics<'a, 'c> {
+    singleton: &'c EcsSingleton,
+    system: &'a Arc<Mutex<System>>,
+}
+
+impl<'a, 'c> Physics<'a, 'c> {
+    pub fn new(singleton: &'c EcsSingleton, system: &'a Arc<Mutex<System>>) -> Self {
+        Physics { singleton, system }
+    }
+
+    pub fn process(&mut self) {
+        for sys in self.system.lock().iter() {
+            for entity in sys.entities().iter() {
+                self.singleton
+                    .get_component_mut::<Transform, _, ()>(*entity, |comp| {
+                        comp.position.x += 1.0;
+                    });
+
+                self.singleton
+                    .get_component_mut::<RigidBody, _, ()>(*entity, |comp| {
+                        comp.force.x += 1.0;
+                    });
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::Deserialize;
+use singleton_ecs::{prelude::*, reflect::ReflectValue};
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Component)]
+#[component(clone, scene, reflect)]
+struct Health {
+    current: i32,
+    max: i32,
+}
+
+singleton_ecs::impl_reflect_struct!(Health { current, max });
+
+#[derive(Component)]
+#[component(storage = "tag")]
+struct Player;
+
+static RELEASED: AtomicUsize = AtomicUsize::new(0);
+
+fn release(handle: &mut Handle, _entity: EntityType) {
+    RELEASED.fetch_add(handle.0, Ordering::SeqCst);
+}
+
+#[derive(Component)]
+#[component(on_destroy = release)]
+struct Handle(usize);
+
+#[derive(Bundle)]
+struct PlayerBundle {
+    health: Health,
+    player: Player,
+}
+
+#[derive(Bundle)]
+struct HandleBundle(Handle, Health);
+
+#[derive(Debug, Default, PartialEq, Resource)]
+struct Score(u32);
+
+#[test]
+pub fn test_derived_component_registration() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register::<Health>();
+    singleton.register::<Player>();
+
+    let infos = singleton.component_infos();
+    let health = infos.iter().find(|info| info.name == "Health").unwrap();
+    assert!(health.cloner.is_some() && health.loader.is_some() && health.reflect.is_some());
+    assert!(infos.iter().any(|info| info.name == "Player" && info.tag));
+
+    let entity = singleton.create_entity();
+    singleton.add_component(entity, Health { current: 3, max: 5 });
+    singleton.add_tag::<Player>(entity);
+    let clone = singleton.clone_entity(entity);
+    assert_eq!(
+        singleton.get_component::<Health>(clone),
+        &Health { current: 3, max: 5 }
+    );
+    assert!(singleton.has_component::<Player>(clone));
+    assert_eq!(
+        singleton.get_component_field(entity, "Health", "max"),
+        Ok(ReflectValue::Int(5))
+    );
+
+    let scene = Scene::from_json(
+        r#"{ "entities": [{ "components": { "Health": { "current": 1, "max": 2 } } }] }"#,
+    )
+    .unwrap();
+    let spawned = singleton.instantiate(&scene).unwrap();
+    assert_eq!(singleton.get_component::<Health>(spawned[0]).max, 2);
+}
+
+#[test]
+pub fn test_on_destroy_hook() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register::<Handle>();
+
+    let kept = singleton.create_entity();
+    singleton.add_component(kept, Handle(100));
+    let entities = singleton.spawn_batch([(Handle(1),), (Handle(2),)]);
+    singleton.destroy_entity(entities[0]);
+    assert_eq!(RELEASED.load(Ordering::SeqCst), 1);
+    singleton.despawn_batch(&entities[1..]);
+    assert_eq!(RELEASED.load(Ordering::SeqCst), 3);
+
+    // Removing the component alone is not a destruction.
+    singleton.remove_component::<Handle>(kept);
+    assert_eq!(RELEASED.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+pub fn test_derived_bundles() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register::<Health>();
+    singleton.register::<Handle>();
+    singleton.register::<Player>();
+
+    let health = Health { current: 1, max: 1 };
+    let handles = singleton.spawn_batch([HandleBundle(Handle(0), health.clone())]);
+    assert!(singleton.has_component::<Handle>(handles[0]));
+    assert_eq!(singleton.get_component::<Health>(handles[0]), &health);
+
+    let players = singleton.spawn_batch((0..3).map(|max| PlayerBundle {
+        health: Health { current: 0, max },
+        player: Player,
+    }));
+    assert_eq!(singleton.get_component::<Health>(players[2]).max, 2);
+    assert!(singleton.has_component::<Player>(players[2]));
+}
+
+#[test]
+pub fn test_init_resource() {
+    let mut singleton = EcsSingleton::new();
+    singleton.init_resource::<Score>();
+    assert_eq!(singleton.get_resource::<Score>(), Some(&Score(0)));
+
+    singleton.get_resource_mut::<Score>().unwrap().0 = 7;
+    singleton.init_resource::<Score>();
+    assert_eq!(singleton.get_resource::<Score>(), Some(&Score(7)));
+}
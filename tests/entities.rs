@@ -0,0 +1,418 @@
+mod common;
+
+use std::{
+    alloc::Layout,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use common::{Physics, RigidBody, Transform};
+use glam::Vec3;
+use singleton_ecs::{clone::CloneError, config::MAX_ENTITIES, prelude::*};
+
+#[test]
+pub fn test_register_entity_to_singleton() {
+    let mut singleton = EcsSingleton::new();
+    // created my components
+    singleton.register_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+
+    let entity = singleton.create_entity();
+
+    let force = Vec3::new(1.0, 1.0, 1.0);
+    singleton.add_component(entity, RigidBody { force });
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    singleton.add_component(entity, Transform { position });
+    let rigid_body = singleton.get_component::<RigidBody>(entity);
+
+    let transform_component = singleton.get_component::<Transform>(entity);
+
+    assert_eq!(transform_component.position, position);
+
+    assert_eq!(rigid_body.force, force);
+}
+
+#[test]
+pub fn test_dynamic_component_matches_system() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    let mana = singleton.register_dynamic_component(
+        "Mana",
+        Layout::new::<u32>(),
+        Some(|_bytes| {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }),
+    );
+    assert_eq!(singleton.get_component_type_by_name("Mana"), Some(mana));
+
+    singleton.register_system::<Physics>();
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    signature.insert(mana as usize);
+    singleton.set_system_signature::<Physics>(signature);
+
+    let entity = singleton.create_entity();
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    singleton.add_component(entity, Transform { position });
+    singleton.add_dynamic_component(entity, mana, &7u32.to_ne_bytes());
+    assert!(singleton
+        .get_system::<Physics>()
+        .lock()
+        .unwrap()
+        .entities()
+        .contains(&entity));
+
+    singleton.get_dynamic_component_mut(entity, mana, |bytes| {
+        bytes.copy_from_slice(&9u32.to_ne_bytes())
+    });
+    let value = singleton.get_dynamic_component(entity, mana, |bytes| {
+        u32::from_ne_bytes(bytes.try_into().unwrap())
+    });
+    assert_eq!(value, 9);
+
+    singleton.remove_dynamic_component(entity, mana);
+    assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+    assert!(!singleton
+        .get_system::<Physics>()
+        .lock()
+        .unwrap()
+        .entities()
+        .contains(&entity));
+}
+
+#[test]
+pub fn test_clone_entity() {
+    #[derive(Clone, Component)]
+    struct Health(u32);
+
+    #[derive(Clone, Component)]
+    #[component(storage = "tag")]
+    struct Elite;
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_cloneable_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+    singleton.register::<Health>();
+    singleton.register::<Elite>();
+
+    let entity = singleton.create_entity();
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    singleton.add_component(entity, Transform { position });
+    let force = Vec3::new(1.0, 1.0, 1.0);
+    singleton.add_component(entity, RigidBody { force });
+
+    let clone = singleton.clone_entity(entity);
+    assert_ne!(clone, entity);
+    assert_eq!(
+        singleton.get_component::<Transform>(clone).position,
+        position
+    );
+    assert!(!singleton.has_component::<RigidBody>(clone));
+
+    assert_eq!(
+        singleton.clone_entity_with(entity, ClonePolicy::ErrorOnNonCloneable),
+        Err(CloneError::NotCloneable("RigidBody".to_string()))
+    );
+
+    singleton.get_component_mut::<Transform, _, ()>(entity, |c| c.position.x = 5.0);
+    singleton.copy_components::<(Transform,)>(entity, clone);
+    assert_eq!(singleton.get_component::<Transform>(clone).position.x, 5.0);
+
+    let bare = singleton.create_entity();
+    singleton.copy_components::<(Health, Transform)>(clone, bare);
+    assert!(!singleton.has_component::<Health>(bare));
+    assert_eq!(singleton.get_component::<Transform>(bare).position.x, 5.0);
+
+    singleton.add_component(clone, Health(7));
+    singleton.copy_components::<(Health, Transform)>(clone, bare);
+    assert_eq!(singleton.get_component::<Health>(bare).0, 7);
+
+    singleton.add_tag::<Elite>(clone);
+    singleton.copy_components::<(Elite, Health)>(clone, bare);
+    assert!(singleton.has_component::<Elite>(bare));
+}
+
+#[test]
+pub fn test_tag_components() {
+    struct Frozen;
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.register_tag::<Frozen>();
+
+    singleton.register_system::<Physics>();
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    signature.insert(singleton.get_component_type::<Frozen>() as usize);
+    singleton.set_system_signature::<Physics>(signature);
+
+    let entity = singleton.create_entity();
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    singleton.add_component(entity, Transform { position });
+    singleton.add_tag::<Frozen>(entity);
+
+    assert!(singleton.has_component::<Frozen>(entity));
+    let names: Vec<String> = singleton
+        .entity_components(entity)
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    assert_eq!(names, vec!["Transform", "Frozen"]);
+    assert!(singleton
+        .get_system::<Physics>()
+        .lock()
+        .unwrap()
+        .entities()
+        .contains(&entity));
+
+    singleton.remove_tag::<Frozen>(entity);
+    assert!(!singleton.has_component::<Frozen>(entity));
+    assert!(singleton
+        .get_system::<Physics>()
+        .lock()
+        .unwrap()
+        .entities()
+        .is_empty());
+}
+
+#[test]
+pub fn test_spawn_and_despawn_batch() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+    singleton.register_system::<Physics>();
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    signature.insert(singleton.get_component_type::<RigidBody>() as usize);
+    singleton.set_system_signature::<Physics>(signature);
+
+    let entities = singleton.spawn_batch((0..100).map(|i| {
+        (
+            Transform {
+                position: Vec3::new(i as f32, 0.0, 0.0),
+            },
+            RigidBody { force: Vec3::ZERO },
+        )
+    }));
+
+    assert_eq!(entities.len(), 100);
+    assert_eq!(
+        singleton
+            .get_component::<Transform>(entities[42])
+            .position
+            .x,
+        42.0
+    );
+    assert_eq!(
+        singleton
+            .get_system::<Physics>()
+            .lock()
+            .unwrap()
+            .entities()
+            .len(),
+        100
+    );
+
+    singleton.despawn_batch(&entities[..50]);
+    assert_eq!(
+        singleton
+            .get_system::<Physics>()
+            .lock()
+            .unwrap()
+            .entities()
+            .len(),
+        50
+    );
+    assert!(!singleton.has_component::<Transform>(entities[0]));
+    assert_eq!(
+        singleton
+            .get_component::<Transform>(entities[99])
+            .position
+            .x,
+        99.0
+    );
+}
+
+#[test]
+pub fn test_living_entity_accounting() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+
+    let first = singleton.create_entity();
+    let second = singleton.create_entity();
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    singleton.add_component(second, Transform { position });
+    assert_eq!(singleton.entity_count(), 2);
+
+    singleton.destroy_entity(first);
+    assert_eq!(singleton.entity_count(), 1);
+    assert!(!singleton.is_alive(first));
+    assert!(singleton.is_alive(second));
+
+    let live: Vec<(u32, usize)> = singleton
+        .entities()
+        .map(|(entity, signature)| (entity, signature.len()))
+        .collect();
+    assert_eq!(live, vec![(second, 1)]);
+}
+
+#[test]
+#[should_panic(expected = "Destroying an entity that is not alive.")]
+pub fn test_double_destroy_is_detected() {
+    let mut singleton = EcsSingleton::new();
+    let entity = singleton.create_entity();
+    singleton.destroy_entity(entity);
+    singleton.destroy_entity(entity);
+}
+
+#[test]
+pub fn test_spawn_batch_rejects_duplicate_types() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<RigidBody>();
+    let force = Vec3::ZERO;
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        singleton.spawn_batch([(RigidBody { force }, RigidBody { force })])
+    }));
+    assert!(result.is_err());
+    assert_eq!(singleton.entity_count(), 0);
+    assert_eq!(singleton.validate(), []);
+}
+
+#[test]
+pub fn test_despawn_batch_checks_ids_first() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    let entities = singleton.spawn_batch((0..3).map(|i| {
+        (Transform {
+            position: Vec3::new(i as f32, 0.0, 0.0),
+        },)
+    }));
+
+    singleton.despawn_batch(&[entities[0], entities[0]]);
+    assert!(!singleton.is_alive(entities[0]));
+    assert_eq!(singleton.validate(), []);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        singleton.despawn_batch(&[entities[1], entities[0]])
+    }));
+    assert!(result.is_err());
+    assert!(singleton.is_alive(entities[1]));
+    assert!(singleton.has_component::<Transform>(entities[1]));
+    assert_eq!(singleton.entity_count(), 2);
+    assert_eq!(singleton.validate(), []);
+}
+
+#[test]
+pub fn test_shared_world_concurrent_read_queries() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    let entities = singleton.spawn_batch((0..8).map(|i| {
+        (Transform {
+            position: Vec3::new(i as f32, 0.0, 0.0),
+        },)
+    }));
+    let world = SharedWorld::new(singleton);
+
+    // Both threads wait at the barrier inside their read scope, so this only
+    // finishes if the two scopes are held at the same time.
+    let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+    let states: Vec<_> = (0..2)
+        .map(|_| world.write(|singleton| singleton.query::<&Transform>()))
+        .collect();
+    let handles: Vec<_> = states
+        .into_iter()
+        .map(|mut state| {
+            let world = world.clone();
+            let barrier = barrier.clone();
+            std::thread::spawn(move || {
+                world.read(|reader| {
+                    barrier.wait();
+                    let mut sum = 0.0;
+                    reader
+                        .query(&mut state)
+                        .for_each(|transform| sum += transform.position.x);
+                    sum
+                })
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 28.0);
+    }
+
+    world.write(|singleton| singleton.destroy_entity(entities[0]));
+    assert_eq!(world.read(|reader| reader.entity_count()), 7);
+    assert!(world.into_inner().is_ok());
+}
+
+#[test]
+pub fn test_move_entity_between_worlds() {
+    struct Frozen;
+
+    let mut simulation = EcsSingleton::new();
+    simulation.register_component::<Transform>();
+    simulation.register_tag::<Frozen>();
+    let mut staging = EcsSingleton::with_registry(simulation.shared_registry());
+    staging.register_component::<RigidBody>();
+    assert_eq!(
+        simulation.get_component_type::<RigidBody>(),
+        staging.get_component_type::<RigidBody>()
+    );
+
+    let _padding = staging.create_entity();
+    let entity = simulation.create_entity();
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    simulation.add_component(entity, Transform { position });
+    simulation.add_tag::<Frozen>(entity);
+
+    let moved = simulation.move_entity(entity, &mut staging);
+
+    assert!(!simulation.is_alive(entity));
+    assert_ne!(moved, entity);
+    assert_eq!(staging.get_component::<Transform>(moved).position, position);
+    assert!(staging.has_component::<Frozen>(moved));
+
+    let entity = simulation.create_entity();
+    simulation.add_component(entity, Transform { position });
+    let mut full = EcsSingleton::with_registry(simulation.shared_registry());
+    for _ in 0..MAX_ENTITIES {
+        full.create_entity();
+    }
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        simulation.move_entity(entity, &mut full)
+    }));
+    assert!(result.is_err());
+    assert_eq!(
+        simulation.get_component::<Transform>(entity).position,
+        position
+    );
+}
+
+#[test]
+pub fn test_destroying_hierarchy_members() {
+    let mut singleton = EcsSingleton::new();
+    let parent = singleton.create_entity();
+    let first = singleton.create_entity();
+    let second = singleton.create_entity();
+    singleton.add_component(first, Parent(parent));
+    singleton.add_component(second, Parent(parent));
+    singleton.add_component(parent, Children(vec![first, second]));
+
+    singleton.destroy_entity(first);
+    assert_eq!(
+        *singleton.get_component::<Children>(parent),
+        Children(vec![second])
+    );
+
+    singleton.destroy_entity(second);
+    assert!(!singleton.has_component::<Children>(parent));
+
+    let child = singleton.create_entity();
+    singleton.add_component(child, Parent(parent));
+    singleton.add_component(parent, Children(vec![child]));
+    singleton.despawn_batch(&[parent]);
+    assert!(!singleton.has_component::<Parent>(child));
+    assert!(singleton.validate().is_empty());
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{RigidBody, Transform};
+use glam::Vec3;
+use singleton_ecs::{prelude::*, registry::FreezeError};
+
+struct Frozen;
+
+struct Marker;
+
+#[derive(Default, Resource)]
+struct Seen(usize);
+
+#[derive(Debug, Clone, PartialEq, Component)]
+#[component(clone)]
+struct Ammo(u32);
+
+#[derive(Component)]
+#[component(storage = "tag")]
+struct Boss;
+
+fn count(mut query: Query<(&Transform, &RigidBody)>, mut seen: ResMut<Seen>) {
+    query.for_each(|_| seen.0 += 1);
+}
+
+/// A world that meets `Transform` and `RigidBody` in the given order.
+fn world(transform_first: bool) -> (EcsSingleton, EntityType) {
+    let mut singleton = EcsSingleton::new();
+    singleton.set_auto_register_components(true);
+    let entity = singleton.create_entity();
+    let transform = Transform {
+        position: Vec3::ZERO,
+    };
+    let body = RigidBody { force: Vec3::X };
+    if transform_first {
+        singleton.add_component(entity, transform);
+        singleton.add_component(entity, body);
+    } else {
+        singleton.add_component(entity, body);
+        singleton.add_component(entity, transform);
+    }
+    (singleton, entity)
+}
+
+#[test]
+pub fn test_components_register_on_first_use() {
+    let (mut singleton, entity) = world(true);
+    singleton.add_tag::<Frozen>(entity);
+    let spawned = singleton.spawn_batch([(Transform { position: Vec3::Y },)]);
+
+    let mut query = singleton.query::<&Transform>();
+    assert_eq!(query.query(&singleton).entities(), [entity, spawned[0]]);
+    assert!(singleton.has_component::<Frozen>(entity));
+    let names: Vec<String> = singleton
+        .component_infos()
+        .into_iter()
+        .map(|info| info.name)
+        .collect();
+    assert_eq!(
+        names[names.len() - 3..],
+        ["Transform", "RigidBody", "Frozen"]
+    );
+    assert_eq!(singleton.validate(), []);
+}
+
+#[test]
+pub fn test_first_use_applies_derived_options() {
+    let mut singleton = EcsSingleton::new();
+    singleton.set_auto_register_components(true);
+    let entity = singleton.create_entity();
+    singleton.add_component(entity, Ammo(3));
+    singleton.add_component(entity, Boss);
+    let spawned = singleton.spawn_batch([(Ammo(5), Boss)]);
+
+    let infos = singleton.component_infos();
+    assert!(infos.iter().any(|info| info.name == "Boss" && info.tag));
+    assert!(infos
+        .iter()
+        .any(|info| info.name == "Ammo" && info.cloner.is_some()));
+    let clone = singleton.clone_entity(entity);
+    assert_eq!(singleton.get_component::<Ammo>(clone), &Ammo(3));
+    assert!(singleton.has_component::<Boss>(clone));
+    assert!(singleton.has_component::<Boss>(spawned[0]));
+    assert_eq!(singleton.validate(), []);
+}
+
+#[test]
+pub fn test_worlds_sharing_a_registry_register_once() {
+    let mut first = EcsSingleton::new();
+    first.set_auto_register_components(true);
+    let worlds: Vec<EcsSingleton> = (0..4)
+        .map(|_| EcsSingleton::with_registry(first.shared_registry()))
+        .collect();
+
+    let threads: Vec<_> = worlds
+        .into_iter()
+        .map(|mut world| {
+            std::thread::spawn(move || {
+                let entity = world.create_entity();
+                world.add_component(entity, Ammo(1));
+                world.get_component_type::<Ammo>()
+            })
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), first.get_component_type::<Ammo>());
+    }
+}
+
+#[test]
+pub fn test_freeze_sorts_component_types_by_name() {
+    let (mut first, _) = world(true);
+    let (mut second, entity) = world(false);
+    assert_ne!(
+        first.get_component_type::<Transform>(),
+        second.get_component_type::<Transform>()
+    );
+
+    second.register_system::<Marker>();
+    let mut signature = Signature::default();
+    signature.insert(second.get_component_type::<RigidBody>() as usize);
+    second.set_system_signature::<Marker>(signature);
+    second.init_resource::<Seen>();
+    second.register_system_func(count);
+    let mut query = second.query::<&Transform>();
+
+    first.freeze_components().unwrap();
+    second.freeze_components().unwrap();
+    for world in [&mut first, &mut second] {
+        assert!(world.get_component_type::<RigidBody>() < world.get_component_type::<Transform>());
+    }
+    assert_eq!(
+        first.get_component_type::<Transform>(),
+        second.get_component_type::<Transform>()
+    );
+
+    // Everything set up before the freeze keeps matching the same entities.
+    assert_eq!(second.validate(), []);
+    assert_eq!(second.system_entities::<Marker>(), [entity]);
+    assert_eq!(query.query(&second).entities(), [entity]);
+    second.update();
+    assert_eq!(second.get_resource::<Seen>().unwrap().0, 1);
+
+    let other = second.create_entity();
+    second.add_component(other, RigidBody { force: Vec3::Y });
+    let mut matched = second.system_entities::<Marker>();
+    matched.sort();
+    assert_eq!(matched, [entity, other]);
+}
+
+#[test]
+#[should_panic(expected = "is not registered; register it first or enable auto-registration")]
+pub fn test_unregistered_component_names_the_type() {
+    let mut singleton = EcsSingleton::new();
+    let entity = singleton.create_entity();
+    singleton.add_component(
+        entity,
+        Transform {
+            position: Vec3::ZERO,
+        },
+    );
+}
+
+#[test]
+#[should_panic(expected = "after the registry was frozen")]
+pub fn test_registering_after_freeze_panics() {
+    let (mut singleton, entity) = world(true);
+    singleton.freeze_components().unwrap();
+    singleton.add_tag::<Frozen>(entity);
+}
+
+#[test]
+pub fn test_freezing_a_shared_registry_fails() {
+    let (mut first, _) = world(true);
+    let mut second = EcsSingleton::with_registry(first.shared_registry());
+    let transform = first.get_component_type::<Transform>();
+
+    assert_eq!(first.freeze_components(), Err(FreezeError::SharedRegistry));
+    assert_eq!(second.get_component_type::<Transform>(), transform);
+
+    drop(second);
+    assert_eq!(first.freeze_components(), Ok(()));
+}
@@ -0,0 +1,105 @@
+use singleton_ecs::prelude::*;
+
+#[test]
+pub fn test_relations_and_cleanup() {
+    struct Targets;
+    struct DockedAt;
+
+    let mut singleton = EcsSingleton::new();
+    singleton
+        .register_relation::<Targets>(RelationArity::ManyToMany, RelationCleanup::RemoveRelation);
+    singleton
+        .register_relation::<DockedAt>(RelationArity::ManyToOne, RelationCleanup::DespawnSource);
+    let targets_type = singleton.get_component_type::<Relation<Targets>>();
+    assert_eq!(
+        singleton.get_component_type_by_name("Relation<Targets>"),
+        Some(targets_type)
+    );
+
+    let station = singleton.create_entity();
+    let enemy = singleton.create_entity();
+    let ship = singleton.create_entity();
+    let drone = singleton.create_entity();
+
+    singleton.add_relation::<Targets>(ship, enemy);
+    singleton.add_relation::<Targets>(ship, station);
+    singleton.add_relation::<Targets>(drone, enemy);
+    singleton.add_relation::<DockedAt>(ship, enemy);
+    singleton.add_relation::<DockedAt>(ship, station);
+    singleton.add_relation::<DockedAt>(drone, ship);
+
+    assert_eq!(singleton.relation_sources::<Targets>(enemy), [ship, drone]);
+    assert!(singleton.relation_sources::<DockedAt>(enemy).is_empty());
+    assert_eq!(singleton.relation_targets::<DockedAt>(ship), [station]);
+    assert_eq!(
+        singleton.get_component::<Relation<Targets>>(ship).targets(),
+        [enemy, station]
+    );
+
+    singleton.destroy_entity(enemy);
+    assert_eq!(singleton.relation_targets::<Targets>(ship), [station]);
+    assert!(!singleton.has_component::<Relation<Targets>>(drone));
+
+    singleton.destroy_entity(station);
+    assert!(!singleton.is_alive(ship));
+    assert!(!singleton.is_alive(drone));
+    assert_eq!(singleton.entity_count(), 0);
+}
+
+#[test]
+pub fn test_removing_the_relation_component_unlinks_it() {
+    struct Targets;
+
+    let mut singleton = EcsSingleton::new();
+    singleton
+        .register_relation::<Targets>(RelationArity::ManyToMany, RelationCleanup::DespawnSource);
+    let enemy = singleton.create_entity();
+    let station = singleton.create_entity();
+    let ship = singleton.create_entity();
+    singleton.add_relation::<Targets>(ship, enemy);
+    singleton.add_relation::<Targets>(ship, station);
+
+    singleton.remove_component::<Relation<Targets>>(ship);
+    assert!(singleton.relation_targets::<Targets>(ship).is_empty());
+    assert!(singleton.relation_sources::<Targets>(enemy).is_empty());
+
+    singleton.destroy_entity(enemy);
+    assert!(singleton.is_alive(ship));
+    singleton.add_relation::<Targets>(ship, station);
+    assert_eq!(singleton.relation_targets::<Targets>(ship), [station]);
+    assert_eq!(singleton.validate(), []);
+}
+
+#[test]
+pub fn test_moving_a_relation_target_keeps_its_sources() {
+    struct DockedAt;
+
+    let mut simulation = EcsSingleton::new();
+    simulation
+        .register_relation::<DockedAt>(RelationArity::ManyToOne, RelationCleanup::DespawnSource);
+    let mut staging = EcsSingleton::with_registry(simulation.shared_registry());
+    let station = simulation.create_entity();
+    let ship = simulation.create_entity();
+    simulation.add_relation::<DockedAt>(ship, station);
+
+    let moved = simulation.move_entity(station, &mut staging);
+    assert!(staging.is_alive(moved));
+    assert!(simulation.is_alive(ship));
+    assert!(!simulation.has_component::<Relation<DockedAt>>(ship));
+    assert!(simulation.relation_sources::<DockedAt>(station).is_empty());
+    assert_eq!(simulation.validate(), []);
+}
+
+#[test]
+#[should_panic(expected = "Relating an entity that is not alive.")]
+pub fn test_relating_a_dead_source_panics() {
+    struct Targets;
+
+    let mut singleton = EcsSingleton::new();
+    singleton
+        .register_relation::<Targets>(RelationArity::ManyToMany, RelationCleanup::RemoveRelation);
+    let target = singleton.create_entity();
+    let source = singleton.create_entity();
+    singleton.destroy_entity(source);
+    singleton.add_relation::<Targets>(source, target);
+}
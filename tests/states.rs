@@ -0,0 +1,99 @@
+mod common;
+
+use common::{Physics, Transform};
+use glam::Vec3;
+use singleton_ecs::prelude::*;
+
+#[test]
+pub fn test_state_transitions() {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum GameState {
+        Menu,
+        Playing,
+    }
+
+    #[derive(Default, Resource)]
+    struct Log(Vec<&'static str>);
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.insert_resource(Log::default());
+    singleton.init_state(GameState::Menu);
+
+    let log = |entry| {
+        move |singleton: &mut EcsSingleton| {
+            singleton.get_resource_mut::<Log>().unwrap().0.push(entry)
+        }
+    };
+    singleton.add_state_system(OnEnter(GameState::Menu), log("enter menu"));
+    singleton.add_state_system(OnExit(GameState::Menu), log("exit menu"));
+    singleton.add_state_system(
+        OnTransition {
+            from: GameState::Menu,
+            to: GameState::Playing,
+        },
+        log("menu -> playing"),
+    );
+    singleton.add_state_system(OnEnter(GameState::Playing), |singleton| {
+        let entity = singleton.create_entity();
+        singleton.add_component(entity, StateScoped(GameState::Playing));
+    });
+
+    singleton.register_system::<Physics>();
+    singleton.set_system_run::<Physics, _>(|singleton, _| {
+        singleton
+            .get_resource_mut::<Log>()
+            .unwrap()
+            .0
+            .push("physics")
+    });
+    singleton.run_if::<Physics, _>(in_state(GameState::Playing));
+
+    let menu_entity = singleton.create_entity();
+    singleton.add_component(menu_entity, StateScoped(GameState::Menu));
+    let persistent = singleton.create_entity();
+    let position = Vec3::ZERO;
+    singleton.add_component(persistent, Transform { position });
+
+    singleton.update();
+    assert_eq!(singleton.get_resource::<Log>().unwrap().0, ["enter menu"]);
+
+    singleton.set_next_state(GameState::Playing);
+    singleton.update();
+    assert_eq!(
+        singleton.get_resource::<Log>().unwrap().0,
+        ["enter menu", "exit menu", "menu -> playing", "physics"]
+    );
+    assert_eq!(
+        singleton.get_resource::<State<GameState>>().unwrap().get(),
+        &GameState::Playing
+    );
+    assert!(!singleton.is_alive(menu_entity));
+    assert!(singleton.is_alive(persistent));
+    assert_eq!(singleton.entity_count(), 2);
+
+    singleton.set_next_state(GameState::Menu);
+    singleton.update();
+    assert_eq!(singleton.entity_count(), 1);
+    assert_eq!(
+        singleton.get_resource::<Log>().unwrap().0.last(),
+        Some(&"enter menu")
+    );
+}
+
+#[test]
+pub fn test_init_state_in_worlds_sharing_a_registry() {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Screen {
+        Title,
+    }
+
+    let mut first = EcsSingleton::new();
+    first.init_state(Screen::Title);
+    let mut second = EcsSingleton::with_registry(first.shared_registry());
+    second.init_state(Screen::Title);
+
+    let entity = second.create_entity();
+    second.add_component(entity, StateScoped(Screen::Title));
+    assert!(second.has_component::<StateScoped<Screen>>(entity));
+}
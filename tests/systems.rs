@@ -0,0 +1,383 @@
+mod common;
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+use common::{Physics, RigidBody, Transform};
+use glam::Vec3;
+use singleton_ecs::prelude::*;
+
+#[test]
+pub fn test_register_system() {
+    let mut singleton = EcsSingleton::new();
+    // created my components
+    singleton.register_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+
+    singleton.register_system::<Physics>();
+
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    signature.insert(singleton.get_component_type::<RigidBody>() as usize);
+    singleton.set_system_signature::<Physics>(signature);
+
+    let entity = singleton.create_entity();
+
+    let force = Vec3::new(1.0, 1.0, 1.0);
+    singleton.add_component(entity, RigidBody { force });
+    let position = Vec3::new(1.0, 0.0, 1.0);
+    singleton.add_component(entity, Transform { position });
+
+    let transform_component = singleton.get_component::<Transform>(entity);
+    let rigid_body = singleton.get_component::<RigidBody>(entity);
+
+    assert_eq!(transform_component.position, position);
+
+    assert_eq!(rigid_body.force, force);
+
+    let gh = singleton.get_system::<Physics>().clone();
+    let mut physics = Physics::new(&singleton, &gh);
+    physics.process();
+
+    let transform_component = singleton.get_component::<Transform>(entity);
+    let rigid_body = singleton.get_component::<RigidBody>(entity);
+
+    assert_eq!(transform_component.position, Vec3::new(2.0, 0.0, 1.0));
+
+    assert_eq!(rigid_body.force, Vec3::new(2.0, 1.0, 1.0));
+}
+
+#[test]
+pub fn test_held_system_follows_later_changes() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.register_system::<Physics>();
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    singleton.set_system_signature::<Physics>(signature);
+    let system = singleton.get_system::<Physics>().clone();
+
+    let entity = singleton.create_entity();
+    let position = Vec3::ZERO;
+    singleton.add_component(entity, Transform { position });
+    assert!(system.lock().unwrap().entities().contains(&entity));
+
+    singleton.remove_component::<Transform>(entity);
+    assert!(system.lock().unwrap().entities().is_empty());
+}
+
+#[test]
+pub fn test_system_run_conditions_and_toggles() {
+    #[derive(PartialEq, Resource)]
+    struct Paused(bool);
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.insert_resource(Paused(false));
+    singleton.register_system::<Physics>();
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    singleton.set_system_signature::<Physics>(signature);
+    singleton.set_system_run::<Physics, _>(|singleton, entities| {
+        for entity in entities {
+            singleton.get_component_mut::<Transform, _, ()>(*entity, |c| c.position.x += 1.0);
+        }
+    });
+    singleton.run_if::<Physics, _>(resource_equals(Paused(false)));
+
+    let entity = singleton.create_entity();
+    let position = Vec3::ZERO;
+    singleton.add_component(entity, Transform { position });
+
+    singleton.run_systems();
+    assert_eq!(singleton.get_component::<Transform>(entity).position.x, 1.0);
+
+    singleton.get_resource_mut::<Paused>().unwrap().0 = true;
+    singleton.run_systems();
+    assert_eq!(singleton.get_component::<Transform>(entity).position.x, 1.0);
+
+    singleton.get_resource_mut::<Paused>().unwrap().0 = false;
+    singleton.set_system_enabled::<Physics>(false);
+    singleton.run_systems();
+    assert_eq!(singleton.get_component::<Transform>(entity).position.x, 1.0);
+    assert!(!singleton.is_system_enabled::<Physics>());
+
+    singleton.set_system_enabled::<Physics>(true);
+    singleton.run_systems();
+    assert_eq!(singleton.get_component::<Transform>(entity).position.x, 2.0);
+
+    singleton.unregister_system::<Physics>();
+    singleton.run_systems();
+    assert_eq!(singleton.get_component::<Transform>(entity).position.x, 2.0);
+}
+
+#[test]
+pub fn test_system_changed_during_its_run() {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    #[derive(Resource)]
+    struct Stopped;
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_system::<Physics>();
+    singleton.set_system_run::<Physics, _>(|singleton, _| {
+        RUNS.fetch_add(1, Ordering::SeqCst);
+        singleton.run_if::<Physics, _>(not(resource_exists::<Stopped>()));
+        singleton.set_system_run::<Physics, _>(|_, _| {
+            RUNS.fetch_add(10, Ordering::SeqCst);
+        });
+    });
+
+    singleton.run_systems();
+    singleton.run_systems();
+    assert_eq!(RUNS.load(Ordering::SeqCst), 11);
+    singleton.insert_resource(Stopped);
+    singleton.run_systems();
+    assert_eq!(RUNS.load(Ordering::SeqCst), 11);
+}
+
+#[test]
+pub fn test_system_survives_a_panicking_run() {
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_system::<Physics>();
+    singleton.set_system_run::<Physics, _>(|_, _| {
+        if RUNS.fetch_add(1, Ordering::SeqCst) == 0 {
+            panic!("first run fails");
+        }
+    });
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| singleton.run_systems()));
+    assert!(result.is_err());
+    singleton.run_systems();
+    assert_eq!(RUNS.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+pub fn test_failed_function_system_returns_its_resources() {
+    #[derive(Resource)]
+    struct Score(u32);
+    #[derive(Resource)]
+    struct Missing;
+
+    fn score(mut score: ResMut<Score>, mut commands: Commands) {
+        score.0 += 1;
+        commands.insert_resource(Missing);
+        panic!("scoring fails");
+    }
+
+    fn needs_missing(_score: Res<Score>, _missing: Res<Missing>) {}
+
+    let mut singleton = EcsSingleton::new();
+    singleton.insert_resource(Score(0));
+    let system = singleton.register_system_func(score);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| singleton.run_systems()));
+    assert!(result.is_err());
+    assert_eq!(singleton.get_resource::<Score>().unwrap().0, 1);
+    assert!(singleton.get_resource::<Missing>().is_none());
+    singleton.unregister_system_by_id(system);
+
+    singleton.register_system_func(needs_missing);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| singleton.run_systems()));
+    assert!(result.is_err());
+    assert_eq!(singleton.get_resource::<Score>().unwrap().0, 1);
+}
+
+#[test]
+pub fn test_function_systems() {
+    #[derive(Resource)]
+    struct Time(f32);
+    struct Hit(EntityType);
+
+    fn gravity(mut query: Query<(&mut Transform, &RigidBody)>, time: Res<Time>) {
+        query.for_each(|(transform, body)| transform.position.x += body.force.x * time.0);
+    }
+
+    fn detect(mut query: Query<&Transform>, mut hits: EventWriter<Hit>) {
+        query.for_each_with_entity(|entity, transform| {
+            if transform.position.x > 1.0 {
+                hits.send(Hit(entity));
+            }
+        });
+    }
+
+    fn despawn_hit(mut hits: EventReader<Hit>, mut commands: Commands) {
+        for Hit(entity) in hits.read() {
+            commands.despawn(*entity);
+        }
+    }
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+    singleton.insert_resource(Time(0.5));
+    singleton.add_event::<Hit>();
+
+    let falling = singleton.create_entity();
+    let position = Vec3::ZERO;
+    singleton.add_component(falling, Transform { position });
+    let force = Vec3::new(2.0, 0.0, 0.0);
+    singleton.add_component(falling, RigidBody { force });
+    let resting = singleton.create_entity();
+    singleton.add_component(resting, RigidBody { force });
+
+    singleton.register_system_func(gravity);
+    let detect = singleton.register_system_func(detect);
+    singleton.register_system_func(despawn_hit);
+    singleton.set_validate_each_tick(true);
+
+    let conflicts = singleton.system_conflicts();
+    assert_eq!(conflicts.len(), 2);
+    assert_eq!(conflicts[0].on, [std::any::type_name::<Transform>()]);
+    assert!(conflicts[1].first.ends_with("detect"));
+
+    singleton.update();
+    assert_eq!(
+        singleton.get_component::<Transform>(falling).position.x,
+        1.0
+    );
+    singleton.update();
+    assert!(!singleton.is_alive(falling));
+    assert!(singleton.is_alive(resting));
+    assert!(singleton.get_resource::<Time>().is_some());
+
+    singleton.unregister_system_by_id(detect);
+    assert!(singleton.system_conflicts().is_empty());
+}
+
+#[test]
+pub fn test_commands_on_resources_a_system_holds() {
+    #[derive(Debug, PartialEq, Resource)]
+    struct Score(u32);
+    #[derive(Debug, Resource)]
+    struct Round(u32);
+
+    fn reset(mut commands: Commands, mut score: ResMut<Score>, round: Res<Round>) {
+        score.0 += round.0;
+        commands.insert_resource(Score(0));
+        commands.add(|singleton| {
+            singleton.remove_resource::<Round>();
+        });
+    }
+
+    let mut singleton = EcsSingleton::new();
+    singleton.insert_resource(Score(5));
+    singleton.insert_resource(Round(1));
+    singleton.register_system_func(reset);
+    singleton.run_systems();
+
+    assert_eq!(singleton.get_resource::<Score>(), Some(&Score(0)));
+    assert!(!singleton.contains_resource::<Round>());
+}
+
+#[test]
+pub fn test_component_and_resource_of_one_type() {
+    fn follow(mut query: Query<&mut Transform>, target: Res<Transform>) {
+        query.for_each(|transform| transform.position = target.position);
+    }
+
+    fn read(_query: Query<&Transform>) {}
+
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.insert_resource(Transform {
+        position: Vec3::ONE,
+    });
+    let entity = singleton.create_entity();
+    singleton.add_component(
+        entity,
+        Transform {
+            position: Vec3::ZERO,
+        },
+    );
+    singleton.register_system_func(follow);
+    singleton.register_system_func(read);
+    singleton.run_systems();
+
+    assert_eq!(
+        singleton.get_component::<Transform>(entity).position,
+        Vec3::ONE
+    );
+    let conflicts = singleton.system_conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].on, [std::any::type_name::<Transform>()]);
+}
+
+#[test]
+pub fn test_query_cache_tracks_structural_changes() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+
+    let position = Vec3::ZERO;
+    let force = Vec3::X;
+    let before = singleton.spawn_batch((0..3).map(|_| (Transform { position },)));
+
+    // Systems registered after the entities still see them.
+    singleton.register_system::<Physics>();
+    let mut signature: Signature = Default::default();
+    signature.insert(singleton.get_component_type::<Transform>() as usize);
+    signature.insert(singleton.get_component_type::<RigidBody>() as usize);
+    singleton.set_system_signature::<Physics>(signature);
+    singleton.add_component(before[1], RigidBody { force });
+    assert_eq!(singleton.system_entities::<Physics>(), [before[1]]);
+
+    let mut query = singleton.query::<(&Transform, &mut RigidBody)>();
+    assert_eq!(query.query(&singleton).entities(), [before[1]]);
+
+    singleton.add_component(before[0], RigidBody { force });
+    singleton.destroy_entity(before[1]);
+    let spawned = singleton.spawn_batch([(Transform { position }, RigidBody { force })]);
+    let mut matched = query.query(&singleton);
+    assert_eq!(matched.entities(), [before[0], spawned[0]]);
+    matched.for_each(|(_, body)| body.force.y = 1.0);
+    drop(matched);
+
+    assert_eq!(
+        singleton.get_component::<RigidBody>(spawned[0]).force.y,
+        1.0
+    );
+    let mut system_entities = singleton.system_entities::<Physics>();
+    system_entities.sort();
+    assert_eq!(system_entities, [before[0], spawned[0]]);
+}
+
+#[test]
+pub fn test_par_for_each() {
+    let mut singleton = EcsSingleton::new();
+    singleton.register_component::<Transform>();
+    singleton.register_component::<RigidBody>();
+
+    let position = Vec3::ZERO;
+    let entities = singleton.spawn_batch((0..1000).map(|i| {
+        let force = Vec3::new(i as f32, 0.0, 0.0);
+        (Transform { position }, RigidBody { force })
+    }));
+
+    let mut query = singleton.query::<(&mut Transform, &RigidBody)>();
+    let visited = AtomicUsize::new(0);
+    query
+        .query(&singleton)
+        .par_for_each(64, |(transform, body)| {
+            transform.position += body.force;
+            visited.fetch_add(1, Ordering::Relaxed);
+        });
+    assert_eq!(visited.load(Ordering::Relaxed), 1000);
+    assert_eq!(
+        singleton
+            .get_component::<Transform>(entities[10])
+            .position
+            .x,
+        10.0
+    );
+
+    singleton.insert_resource(ParallelMode::Deterministic);
+    let order = Mutex::new(Vec::new());
+    query.query(&singleton).par_for_each(64, |(transform, _)| {
+        order.lock().unwrap().push(transform.position.x)
+    });
+    let order = order.into_inner().unwrap();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+}
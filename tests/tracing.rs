@@ -0,0 +1,100 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use common::{RigidBody, Transform};
+use glam::Vec3;
+use singleton_ecs::prelude::*;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// Keeps the message and `component` field of every event.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<(String, String)>>>);
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    component: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "component" {
+            self.component = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{value:?}"),
+            "component" => self.component = format!("{value:?}"),
+            _ => {}
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0
+            .lock()
+            .unwrap()
+            .push((fields.message, fields.component));
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+pub fn test_spawn_batch_traces_each_component() {
+    let recorder = Recorder::default();
+    let events = Arc::clone(&recorder.0);
+
+    tracing::subscriber::with_default(recorder, || {
+        let mut singleton = EcsSingleton::new();
+        singleton.register_component::<Transform>();
+        singleton.register_component::<RigidBody>();
+        singleton.spawn_batch((0..2).map(|_| {
+            (
+                Transform {
+                    position: Vec3::ZERO,
+                },
+                RigidBody { force: Vec3::Y },
+            )
+        }));
+    });
+
+    let events = events.lock().unwrap();
+    let count = |message: &str, component: &str| {
+        events
+            .iter()
+            .filter(|event| event.0 == message && event.1 == component)
+            .count()
+    };
+    assert_eq!(count("entity spawned", ""), 2);
+    assert_eq!(count("component added", "Transform"), 2);
+    assert_eq!(count("component added", "RigidBody"), 2);
+}